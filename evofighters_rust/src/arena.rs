@@ -1,16 +1,37 @@
 use std::cmp::max;
 use std::mem;
+use std::sync::Arc;
+use std::sync::mpsc::Receiver;
+use std::thread;
 use std::time::{Duration, Instant};
 use std::io;
 use std::io::Write;
 
-use creatures::{Creature, Creatures, IDGiver};
+use creatures::{Creature, CreatureID, Creatures, IDGiver};
+use dashboard;
+use dashboard::{Dashboard, Status};
+use diversity;
+use diversity::DiversityReport;
+use dna::lex;
+use hall_of_fame::HallOfFame;
 use eval;
+use lineage::{BirthRecord, LineageLog};
+use metrics::{MetricsLog, Sample};
 use parsing::Decision;
 
 use saver::{OwnedCheckpoint, Saver, Settings};
 use stats::GlobalStatistics;
 use rng::RngState;
+use rules::{Chances, CombatRules, CreatureChance, Rules};
+
+/// Adds a line to an encounter's transcript, if it's keeping one.
+/// The line is only formatted when it will be kept.
+macro_rules! narrate {
+    ($enc:expr, $($arg:tt)*) => (
+        if let Some(ref mut transcript) = $enc.transcript {
+            transcript.push(format!($($arg)*));
+        })
+}
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum FightStatus {
@@ -18,12 +39,6 @@ pub enum FightStatus {
     Continue,
 }
 
-struct CreatureChance {
-    chance_to_hit: usize,
-    dmg_multiplier: usize,
-    mating_share: usize,
-}
-
 impl CreatureChance {
     fn damage(&self, rng: &mut RngState) -> usize {
         if rng.rand_range(1, 101) <= self.chance_to_hit {
@@ -35,175 +50,85 @@ impl CreatureChance {
     }
 }
 
-struct Chances {
-    chance_to_mate: usize,
-    p1: CreatureChance,
-    p2: CreatureChance,
+/// Fire beats ice, ice beats electricity and electricity beats
+/// fire. Returns the percentage of normal damage an attack of the
+/// given type does against an opponent using the other type.
+fn effectiveness(attack: lex::DamageType, other: lex::DamageType) -> usize {
+    use dna::lex::DamageType::{Electricity, Fire, Ice};
+    match (attack, other) {
+        (Fire, Ice) | (Ice, Electricity) | (Electricity, Fire) => 150,
+        (Ice, Fire) | (Electricity, Ice) | (Fire, Electricity) => 50,
+        _ => 100,
+    }
+}
+
+/// Scales the damage of each attack by its effectiveness against the
+/// damage type the opponent is attacking or defending with
+fn apply_effectiveness(
+    chances: &mut Chances,
+    p1_act: eval::PerformableAction,
+    p2_act: eval::PerformableAction,
+) {
+    use eval::PerformableAction::{Attack, Defend};
+    let p1_dmg = match p1_act {
+        Attack(dmg) | Defend(dmg) => Some(dmg),
+        _ => None,
+    };
+    let p2_dmg = match p2_act {
+        Attack(dmg) | Defend(dmg) => Some(dmg),
+        _ => None,
+    };
+    if let (Some(p1_dmg), Some(p2_dmg)) = (p1_dmg, p2_dmg) {
+        if let Attack(..) = p1_act {
+            chances.p1.dmg_multiplier =
+                chances.p1.dmg_multiplier * effectiveness(p1_dmg, p2_dmg) / 100;
+        }
+        if let Attack(..) = p2_act {
+            chances.p2.dmg_multiplier =
+                chances.p2.dmg_multiplier * effectiveness(p2_dmg, p1_dmg) / 100;
+        }
+    }
 }
 
+/// Defending only helps against an attack of the same damage type,
+/// otherwise the attack is treated as if there were no defense at
+/// all. If `type_effectiveness` is on, damage types also have
+/// strengths and weaknesses against each other.
 fn damage_matrix(
     p1_act: eval::PerformableAction,
     p2_act: eval::PerformableAction,
+    combat: &CombatRules,
+    type_effectiveness: bool,
+) -> Chances {
+    let mut chances = base_damage_matrix(p1_act, p2_act, combat);
+    if type_effectiveness {
+        apply_effectiveness(&mut chances, p1_act, p2_act);
+    }
+    chances
+}
+
+fn base_damage_matrix(
+    p1_act: eval::PerformableAction,
+    p2_act: eval::PerformableAction,
+    combat: &CombatRules,
 ) -> Chances {
     use eval::PerformableAction::{Attack, Defend, Mate};
-    // TODO: take into account damage type
     match (p1_act, p2_act) {
-        (Attack(..), Attack(..)) => Chances {
-            chance_to_mate: 0,
-            p1: CreatureChance {
-                chance_to_hit: 75,
-                dmg_multiplier: 50,
-                mating_share: 0,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 75,
-                dmg_multiplier: 50,
-                mating_share: 0,
-            },
-        },
-        (Attack(..), Defend(..)) | (Defend(..), Attack(..)) => Chances {
-            chance_to_mate: 0,
-            p1: CreatureChance {
-                chance_to_hit: 25,
-                dmg_multiplier: 25,
-                mating_share: 0,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 25,
-                dmg_multiplier: 25,
-                mating_share: 0,
-            },
-        },
-        (Attack(..), Mate) => Chances {
-            chance_to_mate: 50,
-            p1: CreatureChance {
-                chance_to_hit: 50,
-                dmg_multiplier: 75,
-                mating_share: 70,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 30,
-            },
-        },
-        (Attack(..), _) => Chances {
-            chance_to_mate: 0,
-            p1: CreatureChance {
-                chance_to_hit: 100,
-                dmg_multiplier: 100,
-                mating_share: 0,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 0,
-            },
-        },
-        (Defend(..), Mate) => Chances {
-            chance_to_mate: 25,
-            p1: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 70,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 30,
-            },
-        },
-        (Mate, Mate) => Chances {
-            chance_to_mate: 100,
-            p1: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 50,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 50,
-            },
-        },
-        (Mate, Attack(..)) => Chances {
-            chance_to_mate: 50,
-            p1: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 30,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 50,
-                dmg_multiplier: 75,
-                mating_share: 70,
-            },
-        },
-        (Mate, Defend(..)) => Chances {
-            chance_to_mate: 25,
-            p1: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 30,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 70,
-            },
-        },
-        (Mate, _) => Chances {
-            chance_to_mate: 75,
-            p1: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 0,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 100,
-            },
-        },
-        (_, Attack(..)) => Chances {
-            chance_to_mate: 0,
-            p1: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 0,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 100,
-                dmg_multiplier: 100,
-                mating_share: 0,
-            },
-        },
-        (_, Mate) => Chances {
-            chance_to_mate: 75,
-            p1: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 100,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 0,
-            },
-        },
-        (_, _) => Chances {
-            chance_to_mate: 0,
-            p1: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 0,
-            },
-            p2: CreatureChance {
-                chance_to_hit: 0,
-                dmg_multiplier: 0,
-                mating_share: 0,
-            },
-        },
+        (Attack(..), Attack(..)) => combat.attack_attack,
+        (Attack(atk), Defend(def)) if atk == def => combat.attack_defend,
+        (Defend(def), Attack(atk)) if atk == def => {
+            combat.attack_defend.swapped()
+        }
+        (Attack(..), Mate) => combat.attack_mate,
+        (Attack(..), _) => combat.attack_other,
+        (Defend(..), Mate) => combat.defend_mate,
+        (Mate, Mate) => combat.mate_mate,
+        (Mate, Attack(..)) => combat.attack_mate.swapped(),
+        (Mate, Defend(..)) => combat.defend_mate.swapped(),
+        (Mate, _) => combat.mate_other,
+        (_, Attack(..)) => combat.attack_other.swapped(),
+        (_, Mate) => combat.mate_other.swapped(),
+        (_, _) => Chances::default(),
     }
 }
 
@@ -215,10 +140,20 @@ fn not_attack_mate_defend(act: eval::PerformableAction) -> bool {
     }
 }
 
+/// Whether the fight ending after `fighter` carries out `action`
+/// means they got away. Dead creatures also end the fight.
+fn is_fleeing(fighter: &Creature, action: eval::PerformableAction) -> bool {
+    action == eval::PerformableAction::Flee
+        && !fighter.is_feeder()
+        && fighter.alive()
+}
+
+#[derive(PartialEq, Eq)]
 enum SimStatus {
     NotStarted,
     EverythingRunningFine,
     NotEnoughCreatures,
+    StoppedByUser,
 }
 
 /// Given an instant and how many events the thread slept for, will
@@ -272,33 +207,54 @@ pub struct Arena {
     rng: RngState,
     population: Creatures,
     settings: Settings,
+    rules: Rules,
     stats: GlobalStatistics,
+    /// Statistics since the last checkpoint
+    window_stats: GlobalStatistics,
+    chronicle: Chronicle,
     total_events: u64,
-    encounters: u64,
     events_since_last_print: u64,
     events_since_last_save: u64,
     rates: RateData,
     saver: Saver,
+    lineage: LineageLog,
+    metrics_log: Option<MetricsLog>,
+    dashboard: Option<Arc<Dashboard>>,
+    /// When the dashboard's leaders were last picked out
+    leaders_updated: Option<Instant>,
+    should_exit: Option<Receiver<()>>,
     sim_status: SimStatus,
 }
 
 impl Arena {
+    /// How many seconds we aim to have between checkpoints
+    const SECONDS_PER_CHECKPOINT: u64 = 30;
+
     pub fn new(
         population: Creatures,
+        rng: RngState,
         filename: &str,
         settings: Settings,
+        rules: Rules,
     ) -> Arena {
         Arena {
-            rng: RngState::default(),
+            rng,
             population,
             settings,
             stats: GlobalStatistics::new(),
+            window_stats: GlobalStatistics::new(),
+            chronicle: Chronicle::new(settings.hall_of_fame_size),
             total_events: 0,
-            encounters: 0,
             events_since_last_print: 0,
             events_since_last_save: 0,
             rates: RateData::initial(),
-            saver: Saver::new(filename),
+            saver: Saver::new(filename, settings, rules.clone()),
+            lineage: LineageLog::for_savefile(filename),
+            metrics_log: None,
+            dashboard: None,
+            leaders_updated: None,
+            should_exit: None,
+            rules,
             sim_status: SimStatus::NotStarted,
         }
     }
@@ -310,10 +266,17 @@ impl Arena {
         let OwnedCheckpoint {
             creatures,
             stats,
+            hall_of_fame,
             settings,
+            rng,
+            rules,
+            ..
         } = checkpoint;
-        let mut arena = Arena::new(creatures, filename, settings);
+        let mut arena =
+            Arena::new(creatures, rng, filename, settings, rules);
         arena.stats = stats;
+        arena.chronicle.hall_of_fame = hall_of_fame;
+        arena.chronicle.event = stats.events;
         arena
     }
 
@@ -324,28 +287,11 @@ impl Arena {
                 self.events_since_last_print,
                 self.settings.metric_fps,
             );
-            print!(
-                "\rCreatures: {creatures}, \
-                 Feeders: {feeders}, \
-                 F/C: {feeder_creature:.3}, \
-                 Mutations: {mutations}, Events: {events}, \
-                 Born: {born}, Eaten: {eaten}, kills: {kills}, \
-                 eps: {eps}, err: {err:.1}%, \
-                 FPS: {fps:.1}       ",
-                creatures = self.population.len(),
-                feeders = self.population.feeder_count(),
-                feeder_creature = self.population.feeder_count() as f64
-                    / self.population.len() as f64,
-                mutations = self.stats.mutations,
-                events = self.total_events,
-                born = self.stats.children_born,
-                eaten = self.stats.feeders_eaten,
-                kills = self.stats.kills,
-                eps = self.rates.events_per_second,
-                err = self.rates.prediction_error * 100.0,
-                fps = self.rates.fps,
-            );
-            io::stdout().flush();
+            if !dashboard::terminal_taken(&self.dashboard) {
+                self.print_status();
+            }
+            self.record_metrics();
+            self.update_dashboard();
             self.events_since_last_print = 0;
             Instant::now()
         } else {
@@ -353,99 +299,451 @@ impl Arena {
         }
     }
 
+    fn print_status(&self) {
+        print!(
+            "\rCreatures: {creatures}, \
+             Feeders: {feeders}, \
+             F/C: {feeder_creature:.3}, \
+             Mutations: {mutations}, Events: {events}, \
+             Born: {born}, Eaten: {eaten}, kills: {kills}, \
+             eps: {eps}, err: {err:.1}%, \
+             FPS: {fps:.1}       ",
+            creatures = self.population.len(),
+            feeders = self.population.feeder_count(),
+            feeder_creature = self.population.feeder_count() as f64
+                / self.population.len() as f64,
+            mutations = self.stats.mutations,
+            events = self.total_events,
+            born = self.stats.children_born,
+            eaten = self.stats.feeders_eaten,
+            kills = self.stats.kills,
+            eps = self.rates.events_per_second,
+            err = self.rates.prediction_error * 100.0,
+            fps = self.rates.fps,
+        );
+        io::stdout().flush();
+    }
+
+    fn record_metrics(&mut self) {
+        if let Some(ref mut log) = self.metrics_log {
+            let sample = Sample::new(
+                self.population.len(),
+                self.population.feeder_count(),
+                &self.stats,
+                self.rates.events_per_second,
+            );
+            if let Err(e) = log.record(&sample) {
+                dashboard::announce(
+                    &self.dashboard,
+                    &format!("\nFailed to write metrics: {}", e),
+                );
+            }
+        }
+    }
+
+    fn update_dashboard(&mut self) {
+        if let Some(ref dashboard) = self.dashboard {
+            dashboard.update(Status {
+                population: self.population.len(),
+                feeders: self.population.feeder_count(),
+                events_per_second: self.rates.events_per_second,
+                prediction_error: Some(self.rates.prediction_error),
+                fps: self.rates.fps,
+                paused: false,
+                stats: self.stats,
+            });
+            // Only the terminal UI shows them
+            let due = self.leaders_updated.is_none_or(|updated| {
+                updated.elapsed() >= Dashboard::LEADERS_INTERVAL
+            });
+            if due && dashboard.owns_terminal() {
+                dashboard.update_leaders(&self.population);
+                self.leaders_updated = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Pauses, saves or stops if someone asked for it on the
+    /// dashboard
+    fn obey_dashboard(&mut self) {
+        let dashboard = match self.dashboard {
+            Some(ref dashboard) => Arc::clone(dashboard),
+            None => return,
+        };
+        if dashboard.is_paused() {
+            self.update_dashboard();
+            self.announce("\nPaused from the dashboard");
+            dashboard.wait_while_paused();
+            self.announce("Resumed");
+        }
+        if dashboard.quit_requested() {
+            self.announce("\nSaving before stopping");
+            self.final_save();
+            self.sim_status = SimStatus::StoppedByUser;
+        } else if dashboard.take_save_request() {
+            match self.save() {
+                Ok(()) => self.announce("\nSaved to file from the dashboard"),
+                Err(e) => self.announce(&format!("\nFailed to save: {}", e)),
+            }
+        }
+    }
+
+    /// Saves and stops if a graceful shutdown message has arrived
+    fn obey_signals(&mut self) {
+        let exiting = self.should_exit
+            .as_ref()
+            .is_some_and(|should_exit| should_exit.try_recv().is_ok());
+        if exiting {
+            self.announce("\nCaught a signal, saving before exiting");
+            self.final_save();
+            self.sim_status = SimStatus::StoppedByUser;
+        }
+    }
+
+    fn announce(&self, message: &str) {
+        dashboard::announce(&self.dashboard, message);
+    }
+
+    /// Saves a checkpoint about every `SECONDS_PER_CHECKPOINT`. If the
+    /// save fails, it's tried again `Saver::RETRY_SECONDS` later.
     fn maybe_save(&mut self) {
-        if self.rates.events_per_second > 0
-            && self.rates.events_per_second * 30 <= self.events_since_last_save
+        let eps = self.rates.events_per_second;
+        if eps > 0
+            && eps * Arena::SECONDS_PER_CHECKPOINT
+                <= self.events_since_last_save
         {
-            println!(
+            self.announce(&format!(
                 "\nHit {} out of estimated {} events, one moment...",
-                self.rates.events_per_second * 30,
+                eps * Arena::SECONDS_PER_CHECKPOINT,
                 self.events_since_last_save,
-            );
-            // TODO: handle failed saves gracefully?
-            self.saver.save(&self.population, &self.stats).unwrap();
-            println!("Saved to file");
+            ));
+            if let Err(e) = self.save() {
+                self.announce(&format!(
+                    "Failed to save: {}, trying again in {} seconds",
+                    e,
+                    Saver::RETRY_SECONDS
+                ));
+                self.events_since_last_save = eps
+                    * (Arena::SECONDS_PER_CHECKPOINT - Saver::RETRY_SECONDS);
+                return;
+            }
+            self.announce("Saved to file");
+            self.announce(&self.window_stats.behaviour());
+            let mut report = diversity::analyze(&self.population);
+            report.species.truncate(DiversityReport::SHOWN_SPECIES);
+            self.announce(report.to_string().trim_end());
+            self.window_stats = GlobalStatistics::new();
             self.events_since_last_save = 0;
         }
     }
 
+    /// Saves before stopping, trying a few times since there won't be
+    /// another chance
+    fn final_save(&mut self) {
+        for attempt in 1..=Saver::FINAL_ATTEMPTS {
+            match self.save() {
+                Ok(()) => return self.announce("Saved to file"),
+                Err(e) => self.announce(&format!("Failed to save: {}", e)),
+            }
+            if attempt < Saver::FINAL_ATTEMPTS {
+                thread::sleep(Duration::from_secs(Saver::RETRY_SECONDS));
+            }
+        }
+        self.announce("Giving up, the last checkpoint is all that's left");
+    }
+
+    /// Saves a checkpoint with everything needed to resume the
+    /// simulation exactly where it is now, and adds the births since
+    /// the last checkpoint to the lineage log
+    pub fn save(&mut self) -> io::Result<()> {
+        self.saver.save(
+            &self.population,
+            &self.stats,
+            &self.chronicle.hall_of_fame,
+            &self.rng,
+            &[],
+        )?;
+        self.lineage.append(&self.chronicle.births)?;
+        self.chronicle.births.clear();
+        Ok(())
+    }
+
+    /// Appends a sample to `log` every time the status is printed
+    pub fn log_metrics(&mut self, log: MetricsLog) {
+        self.metrics_log = Some(log);
+    }
+
+    /// Whether to add births to the lineage log, which is on unless
+    /// this turns it off
+    pub fn log_lineage(&mut self, lineage: bool) {
+        self.chronicle.keep_births = lineage;
+    }
+
+    /// Reports the status to `dashboard` whenever it's printed, and
+    /// takes save, pause and quit requests from it
+    pub fn attach_dashboard(&mut self, dashboard: Arc<Dashboard>) {
+        self.dashboard = Some(dashboard);
+    }
+
+    /// Saves and stops simulating once a message arrives on
+    /// `should_exit`
+    pub fn exit_on(&mut self, should_exit: Receiver<()>) {
+        self.should_exit = Some(should_exit);
+    }
+
+    pub fn population(&self) -> &Creatures {
+        &self.population
+    }
+
+    pub fn stats(&self) -> &GlobalStatistics {
+        &self.stats
+    }
+
+    pub fn hall_of_fame(&self) -> &HallOfFame {
+        &self.chronicle.hall_of_fame
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn total_events(&self) -> u64 {
+        self.total_events
+    }
+
+    /// Change the settings of a running arena. They take effect from
+    /// the next event, and are saved with the next checkpoint.
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.population
+            .set_max_pop_size(settings.max_population_size);
+        self.saver.set_settings(settings);
+        self.settings = settings;
+    }
+
+    /// Runs up to `events` events, stopping early if there aren't
+    /// enough creatures left for an encounter. Returns how many events
+    /// were run.
+    pub fn run(&mut self, events: u64) -> u64 {
+        let mut events_run = 0;
+        while events_run < events && self.population.len() >= 2 {
+            self.step();
+            events_run += 1;
+        }
+        events_run
+    }
+
+    /// Takes two particular creatures out of the population and has
+    /// them encounter each other. An id of 0 stands for a feeder.
+    pub fn stage_fight(
+        &mut self,
+        id1: CreatureID,
+        id2: CreatureID,
+    ) -> Result<GlobalStatistics, String> {
+        if id1 == id2 {
+            return Err("A creature can't fight itself".to_owned());
+        }
+        let p1 = self.take_fighter(id1)?;
+        let p2 = match self.take_fighter(id2) {
+            Ok(p2) => p2,
+            Err(err) => {
+                self.population.absorb(p1);
+                return Err(err);
+            }
+        };
+        info!("{} is made to fight {}", p1, p2);
+        let enc_stats = run_encounter(
+            &mut self.population,
+            p1,
+            p2,
+            &mut self.chronicle,
+            &mut self.rng,
+            &self.settings,
+            &self.rules,
+        );
+        self.stats.absorb(enc_stats);
+        Ok(enc_stats)
+    }
+
+    fn take_fighter(&mut self, id: CreatureID) -> Result<Creature, String> {
+        if id.is_feeder() {
+            self.population.refill_feeders();
+            if self.population.feeder_count() == 0 {
+                return Err("There are no feeders right now".to_owned());
+            }
+            return Ok(self.population.take_feeder());
+        }
+        self.population
+            .take(id)
+            .ok_or_else(|| format!("No creature with id {}", id))
+    }
+
+    /// Runs a single event in the arena
+    pub fn step(&mut self) {
+        let enc_stats = run_event(
+            &mut self.population,
+            &mut self.chronicle,
+            &mut self.rng,
+            &self.settings,
+            &self.rules,
+        );
+        self.stats.absorb(enc_stats);
+        self.window_stats.absorb(enc_stats);
+
+        self.total_events += 1;
+        self.events_since_last_save += 1;
+        self.events_since_last_print += 1;
+    }
+
     pub fn simulate(&mut self) {
         let mut timestamp = Instant::now();
         self.sim_status = SimStatus::EverythingRunningFine;
         while self.population.len() >= 2 {
             timestamp = self.maybe_print_status(timestamp);
-            self.maybe_save();
-            self.population.refill_feeders();
-            let p1 = self.population.random_creature();
-            let p2 = self.population.random_creature_or_feeder();
-
-            info!("{} encounters {} in the wild", p1, p2);
-            if !p1.is_feeder() && !p2.is_feeder() {
-                self.encounters += 1;
+            self.obey_dashboard();
+            self.obey_signals();
+            if self.sim_status == SimStatus::StoppedByUser {
+                break;
             }
-            let mut enc = Encounter::new(
-                p1,
-                p2,
-                self.settings.mutation_rate,
-                &mut self.rng,
-                self.population.id_giver(),
-            );
-            enc.encounter();
-            let Encounter {
-                children,
-                p1,
-                p2,
-                stats: enc_stats,
-                ..
-            } = enc;
-            self.stats.absorb(enc_stats);
-            self.population.absorb_all(children);
-            self.population.absorb(p1);
-            self.population.absorb(p2);
-
-            self.total_events += 1;
-            self.events_since_last_save += 1;
-            self.events_since_last_print += 1;
-        }
-        self.sim_status = SimStatus::NotEnoughCreatures;
+            self.maybe_save();
+            self.step();
+        }
+        if self.population.len() < 2 {
+            self.sim_status = SimStatus::NotEnoughCreatures;
+        }
         match self.sim_status {
             SimStatus::NotEnoughCreatures => {
-                println!(
+                self.announce(
                     "You need at least two creatures in your population \
                      to have an encounter. Unfortunately, this means the \
-                     end for your population."
+                     end for your population.",
                 );
                 if self.population.len() == 1 {
-                    println!(
+                    let last = self.population.random_creature();
+                    self.announce(&format!(
                         "Here is the last of its kind:\n{:?}",
-                        self.population.random_creature()
-                    )
+                        last
+                    ))
                 }
             }
+            SimStatus::StoppedByUser => self.announce("Stopped"),
             _ => unreachable!(),
         }
     }
 }
 
+/// What's remembered about events after the creatures in them are
+/// gone: the champions that died and who was born to whom
+pub struct Chronicle {
+    pub hall_of_fame: HallOfFame,
+    /// Births that haven't been written to the lineage log yet
+    pub births: Vec<BirthRecord>,
+    /// Whether births are kept for the lineage log at all
+    pub keep_births: bool,
+    /// The worker thread the events are on
+    pub worker: u16,
+    /// The number of the next event
+    pub event: u64,
+}
+
+impl Chronicle {
+    pub fn new(hall_of_fame_size: usize) -> Chronicle {
+        Chronicle {
+            hall_of_fame: HallOfFame::new(hall_of_fame_size),
+            births: Vec::new(),
+            keep_births: true,
+            worker: 0,
+            event: 0,
+        }
+    }
+}
+
+/// Runs a single event: a random creature from the population
+/// encounters a random creature or feeder, and the survivors and any
+/// children are put back into the population, and the dead are
+/// considered for the hall of fame. Returns the statistics from the
+/// encounter.
+pub fn run_event(
+    population: &mut Creatures,
+    chronicle: &mut Chronicle,
+    rng: &mut RngState,
+    settings: &Settings,
+    rules: &Rules,
+) -> GlobalStatistics {
+    population.refill_feeders();
+    let p1 = population.random_creature();
+    let p2 = population.random_creature_or_feeder();
+
+    info!("{} encounters {} in the wild", p1, p2);
+    run_encounter(population, p1, p2, chronicle, rng, settings, rules)
+}
+
+/// Runs an encounter between two creatures that have been taken out
+/// of the population, then puts the survivors and any children back.
+/// This counts as an event in the `chronicle`.
+pub fn run_encounter(
+    population: &mut Creatures,
+    p1: Creature,
+    p2: Creature,
+    chronicle: &mut Chronicle,
+    rng: &mut RngState,
+    settings: &Settings,
+    rules: &Rules,
+) -> GlobalStatistics {
+    let mut enc =
+        Encounter::new(p1, p2, settings, rules, rng, population.id_giver());
+    enc.encounter();
+    let Encounter {
+        children,
+        p1,
+        p2,
+        mut stats,
+        ..
+    } = enc;
+    for fighter in &[&p1, &p2] {
+        if fighter.dead() {
+            chronicle.hall_of_fame.consider(fighter);
+        }
+    }
+    if chronicle.keep_births {
+        for child in &children {
+            chronicle.births.push(BirthRecord::new(
+                child,
+                chronicle.worker,
+                chronicle.event,
+            ));
+        }
+    }
+    chronicle.event += 1;
+    stats.events = 1;
+    population.absorb_all(children);
+    population.absorb(p1);
+    population.absorb(p2);
+    stats
+}
+
 pub struct Encounter<'a> {
     pub p1: Creature,
     pub p2: Creature,
     pub stats: GlobalStatistics,
     pub children: Vec<Creature>,
+    rules: &'a Rules,
     rng: &'a mut RngState,
     id_giver: &'a mut IDGiver,
 
     max_rounds: usize,
     mutation_rate: f64,
+    type_effectiveness: bool,
     p1_action: eval::PerformableAction,
     p2_action: eval::PerformableAction,
+    transcript: Option<Vec<String>>,
 }
 
 impl<'a> Encounter<'a> {
     pub fn new(
         p1: Creature,
         p2: Creature,
-        mutation_rate: f64,
+        settings: &Settings,
+        rules: &'a Rules,
         rng: &'a mut RngState,
         id_giver: &'a mut IDGiver,
     ) -> Encounter<'a> {
@@ -455,48 +753,86 @@ impl<'a> Encounter<'a> {
             p2,
             stats: GlobalStatistics::new(),
             children: Vec::new(),
+            rules,
             rng,
             id_giver,
             max_rounds,
-            mutation_rate,
+            mutation_rate: settings.mutation_rate,
+            type_effectiveness: settings.type_effectiveness,
             p1_action: eval::PerformableAction::NoAction,
             p2_action: eval::PerformableAction::NoAction,
+            transcript: None,
+        }
+    }
+
+    /// Keep a human readable, round by round account of the
+    /// encounter. This is slow, so it's only meant for encounters
+    /// someone is going to look at.
+    pub fn keep_transcript(&mut self) {
+        self.transcript = Some(Vec::new());
+    }
+
+    pub fn transcript(&self) -> &[String] {
+        match self.transcript {
+            Some(ref transcript) => transcript,
+            None => &[],
         }
     }
 
     fn both_decided(
         &mut self,
-        &Decision {
+        decision1: &Decision,
+        decision2: &Decision,
+    ) -> FightStatus {
+        let Decision {
             tree: ref tree1,
             icount: i1,
             skipped: s1,
             ..
-        }: &Decision,
-        &Decision {
+        } = *decision1;
+        let Decision {
             tree: ref tree2,
             icount: i2,
             skipped: s2,
             ..
-        }: &Decision,
-    ) -> FightStatus {
+        } = *decision2;
         debug!("{} thinks {:?}", self.p1, tree1);
         debug!("{} thinks {:?}", self.p2, tree2);
-        self.p1_action = eval::evaluate(&self.p1, &self.p2, tree1);
-        self.p2_action = eval::evaluate(&self.p2, &self.p1, tree2);
+        self.p1_action = decision1.program().run(&self.p1, &self.p2);
+        self.p2_action = decision2.program().run(&self.p2, &self.p1);
+        debug_assert_eq!(
+            self.p1_action,
+            eval::evaluate(&self.p1, &self.p2, tree1)
+        );
+        debug_assert_eq!(
+            self.p2_action,
+            eval::evaluate(&self.p2, &self.p1, tree2)
+        );
+        for &(fighter, action) in &[
+            (&self.p1, self.p1_action),
+            (&self.p2, self.p2_action),
+        ] {
+            if !fighter.is_feeder() {
+                self.stats.actions.count(action);
+            }
+        }
+        narrate!(self, "{} thinks:\n{}", self.p1, tree1.to_string().trim());
+        narrate!(self, "{} decides to {}", self.p1, self.p1_action);
+        narrate!(self, "{} thinks:\n{}", self.p2, tree2.to_string().trim());
+        narrate!(self, "{} decides to {}", self.p2, self.p2_action);
         let (p1_cost, p2_cost) = (i1 + s1, i2 + s2);
-        if p1_cost < p2_cost {
-            trace!("{} is going first", self.p1);
-            trace!("{} intends to {}", self.p1, self.p1_action);
-            self.do_round()
-        } else if p2_cost > p1_cost {
-            trace!("{} is going first", self.p2);
-            trace!("{} intends to {}", self.p2, self.p2_action);
-            self.do_swapped_round()
-        } else if self.rng.rand() {
+        let p1_first = if p1_cost != p2_cost {
+            p1_cost < p2_cost
+        } else {
+            self.rng.rand()
+        };
+        if p1_first {
             trace!("{} is going first", self.p1);
+            narrate!(self, "{} goes first", self.p1);
             self.do_round()
         } else {
             trace!("{} is going first", self.p2);
+            narrate!(self, "{} goes first", self.p2);
             self.do_swapped_round()
         }
     }
@@ -507,6 +843,15 @@ impl<'a> Encounter<'a> {
         let mut fight_timed_out = true;
         for round in 0..self.max_rounds {
             debug!("Round {}", round);
+            narrate!(
+                self,
+                "Round {}: {} has {} energy, {} has {} energy",
+                round + 1,
+                self.p1,
+                self.p1.energy(),
+                self.p2,
+                self.p2.energy()
+            );
             let p1_decision = self.p1.next_decision();
             let p2_decision = self.p2.next_decision();
             self.stats.rounds += 1;
@@ -519,8 +864,15 @@ impl<'a> Encounter<'a> {
             self.p2.last_action = self.p2_action;
         }
         if fight_timed_out {
+            self.stats.outcomes.timeouts += 1;
             let penalty = self.rng.rand_range(1, 7);
             info!("Time is up! both combatants take {} damage", penalty);
+            narrate!(
+                self,
+                "Time is up after {} rounds! Both take {} damage",
+                self.max_rounds,
+                penalty
+            );
             self.p1.lose_energy(penalty);
             self.p2.lose_energy(penalty);
         }
@@ -530,8 +882,11 @@ impl<'a> Encounter<'a> {
             self.swap_players();
             self.victory();
         } else if self.p1.dead() && self.p2.dead() {
-            info!("Both {} and {} have died.", self.p1, self.p2)
+            self.stats.outcomes.mutual_deaths += 1;
+            info!("Both {} and {} have died.", self.p1, self.p2);
+            narrate!(self, "Both {} and {} have died", self.p1, self.p2);
         } else {
+            narrate!(self, "Both {} and {} survive", self.p1, self.p2);
             self.p1.survived_encounter();
             self.p2.survived_encounter();
         }
@@ -543,9 +898,20 @@ impl<'a> Encounter<'a> {
         first_share: usize,
         second_share: usize,
     ) -> Option<Creature> {
-        if self.rng.rand_range(1, 101) > mating_chance || self.p2.dead()
-            || self.p1.dead()
-        {
+        let roll = self.rng.rand_range(1, 101);
+        if self.p2.dead() || self.p1.dead() {
+            return None;
+        }
+        if mating_chance > 0 {
+            narrate!(
+                self,
+                "Mating chance is {}%, rolled {}: {}",
+                mating_chance,
+                roll,
+                if roll > mating_chance { "no luck" } else { "success" }
+            );
+        }
+        if roll > mating_chance {
             return None;
         }
         info!("{} tried to mate with {}!", self.p2, self.p1);
@@ -558,15 +924,18 @@ impl<'a> Encounter<'a> {
             if self.p1.is_feeder() {
                 self.p1.kill();
             }
+            narrate!(self, "Mating with a feeder just kills it");
             return None;
         }
         debug!("Attempting to mate");
-        if self.p2.pay_for_mating(first_share)
-            && self.p1.pay_for_mating(second_share)
+        if self.p2.pay_for_mating(first_share, self.rules)
+            && self.p1.pay_for_mating(second_share, self.rules)
         {
             debug!("Both paid their debts, so they get to mate");
             self.mate()
         } else {
+            self.stats.outcomes.failed_mating_payments += 1;
+            narrate!(self, "Mating fails, someone couldn't pay for it");
             None
         }
     }
@@ -577,11 +946,14 @@ impl<'a> Encounter<'a> {
             &mut self.id_giver,
             &mut self.rng,
             self.mutation_rate,
+            self.rules,
         );
         self.stats.absorb(stats);
         match maybe_child {
             Err(_) => {
+                self.stats.outcomes.stillbirths += 1;
                 info!("Child didn't live since it had invalid dna.");
+                narrate!(self, "The child is stillborn, its DNA is invalid");
                 None
             }
             Ok(child) => {
@@ -589,6 +961,13 @@ impl<'a> Encounter<'a> {
                     "{} and {} have a child named {}",
                     self.p1, self.p2, child
                 );
+                narrate!(
+                    self,
+                    "{} and {} have a child named {}",
+                    self.p1,
+                    self.p2,
+                    child
+                );
                 Some(child)
             }
         }
@@ -596,20 +975,53 @@ impl<'a> Encounter<'a> {
 
     fn victory(&mut self) {
         info!("{} has killed {}", self.p1, self.p2);
-        self.p1.steal_from(&mut self.p2);
+        self.p1.steal_from(&mut self.p2, self.rules);
         if self.p2.is_feeder() {
+            narrate!(self, "{} eats {}", self.p1, self.p2);
             self.stats.feeders_eaten += 1;
             self.p1.has_eaten();
-            self.p1.gain_energy(self.rng.rand_range(0, 1));
+            self.p1.gain_energy(self.rng.rand_range(0, 1), self.rules);
             self.p1.last_action = eval::PerformableAction::Wait;
         } else {
-            self.p1.gain_winner_energy(&mut self.rng);
+            narrate!(self, "{} has killed {}", self.p1, self.p2);
+            self.p1.gain_winner_energy(&mut self.rng, self.rules);
             self.p1.has_killed();
             self.stats.kills += 1;
             self.p1.survived_encounter();
         }
     }
 
+    /// If p1 is about to try to flee, say how the attempt will go
+    fn narrate_flee(&mut self) {
+        if self.transcript.is_none()
+            || self.p1_action != eval::PerformableAction::Flee
+            || self.p1.is_feeder() || self.p1.dead()
+        {
+            return;
+        }
+        let attempt = self.p1.flee_attempt(&self.p2);
+        if attempt.escaped() {
+            narrate!(
+                self,
+                "{} rolls {} against {}'s {} and escapes, taking {} damage",
+                self.p1,
+                attempt.my_roll,
+                self.p2,
+                attempt.other_roll,
+                attempt.damage
+            );
+        } else {
+            narrate!(
+                self,
+                "{} rolls {} against {}'s {} and can't get away",
+                self.p1,
+                attempt.my_roll,
+                self.p2,
+                attempt.other_roll
+            );
+        }
+    }
+
     //swap the players in this encounter, some things are dependent on order
     fn swap_players(&mut self) {
         mem::swap(&mut self.p1, &mut self.p2);
@@ -624,16 +1036,23 @@ impl<'a> Encounter<'a> {
     }
 
     fn do_round(&mut self) -> FightStatus {
-        let chances = damage_matrix(self.p1_action, self.p2_action);
+        let chances = damage_matrix(
+            self.p1_action,
+            self.p2_action,
+            &self.rules.combat,
+            self.type_effectiveness,
+        );
         let p1_dmg = chances.p1.damage(&mut self.rng);
         let p2_dmg = chances.p2.damage(&mut self.rng);
         if p1_dmg > 0 {
             info!("{} takes {} damage", self.p2, p1_dmg);
+            narrate!(self, "{} takes {} damage", self.p2, p1_dmg);
             self.p2.lose_energy(p1_dmg)
         }
         if p2_dmg > 0 {
             info!("{} takes {} damage", self.p1, p2_dmg);
-            self.p2.lose_energy(p2_dmg)
+            narrate!(self, "{} takes {} damage", self.p1, p2_dmg);
+            self.p1.lose_energy(p2_dmg)
         }
 
         // we reverse the order of p1, p2 when calling try_to_mate because
@@ -652,16 +1071,28 @@ impl<'a> Encounter<'a> {
         };
 
         if not_attack_mate_defend(self.p1_action) {
+            self.narrate_flee();
+            let fleeing = is_fleeing(&self.p1, self.p1_action);
             if let FightStatus::End =
-                self.p1.carryout(&mut self.p2, self.p1_action)
+                self.p1.carryout(&mut self.p2, self.p1_action, self.rules)
             {
+                if fleeing {
+                    self.stats.outcomes.flee_successes += 1;
+                }
                 return FightStatus::End;
             }
         }
         if not_attack_mate_defend(self.p2_action) {
+            self.swap_players();
+            self.narrate_flee();
+            self.swap_players();
+            let fleeing = is_fleeing(&self.p2, self.p2_action);
             if let FightStatus::End =
-                self.p2.carryout(&mut self.p1, self.p2_action)
+                self.p2.carryout(&mut self.p1, self.p2_action, self.rules)
             {
+                if fleeing {
+                    self.stats.outcomes.flee_successes += 1;
+                }
                 return FightStatus::End;
             }
         }
@@ -670,3 +1101,241 @@ impl<'a> Encounter<'a> {
         FightStatus::Continue
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use serde_json;
+    use saver::SettingsBuilder;
+    use dna::lex::DamageType::{Electricity, Fire, Ice};
+    use eval::PerformableAction::{Attack, Defend};
+    use test::Bencher;
+
+    fn default_matrix(
+        p1_act: eval::PerformableAction,
+        p2_act: eval::PerformableAction,
+        type_effectiveness: bool,
+    ) -> Chances {
+        let combat = Rules::default().combat;
+        damage_matrix(p1_act, p2_act, &combat, type_effectiveness)
+    }
+
+    #[test]
+    fn matching_defense_blocks_attack() {
+        let chances = default_matrix(Attack(Fire), Defend(Fire), false);
+        assert_eq!(chances.p1.chance_to_hit, 25);
+        assert_eq!(chances.p1.dmg_multiplier, 25);
+    }
+
+    #[test]
+    fn mismatched_defense_does_not_block_attack() {
+        let chances = default_matrix(Attack(Fire), Defend(Ice), false);
+        assert_eq!(chances.p1.chance_to_hit, 100);
+        assert_eq!(chances.p1.dmg_multiplier, 100);
+        assert_eq!(chances.p2.chance_to_hit, 0);
+
+        let chances = default_matrix(Defend(Ice), Attack(Fire), false);
+        assert_eq!(chances.p1.chance_to_hit, 0);
+        assert_eq!(chances.p2.chance_to_hit, 100);
+        assert_eq!(chances.p2.dmg_multiplier, 100);
+    }
+
+    #[test]
+    fn type_effectiveness_scales_damage() {
+        let chances = default_matrix(Attack(Fire), Attack(Ice), true);
+        assert_eq!(chances.p1.dmg_multiplier, 75);
+        assert_eq!(chances.p2.dmg_multiplier, 25);
+
+        let chances =
+            default_matrix(Attack(Fire), Defend(Electricity), true);
+        assert_eq!(chances.p1.dmg_multiplier, 50);
+
+        let chances = default_matrix(Attack(Fire), Attack(Ice), false);
+        assert_eq!(chances.p1.dmg_multiplier, 50);
+        assert_eq!(chances.p2.dmg_multiplier, 50);
+    }
+
+    #[test]
+    fn transcript_is_only_kept_when_asked_for() {
+        let rules = Rules::default();
+        let settings = Settings::default();
+        let mut rng = RngState::from_seed(1);
+        let mut id_giver = IDGiver::unthreaded();
+        let seed1 =
+            Creature::seed_creature(id_giver.next_creature_id(), &rules);
+        let seed2 =
+            Creature::seed_creature(id_giver.next_creature_id(), &rules);
+
+        let mut enc = Encounter::new(
+            seed1.clone(),
+            seed2.clone(),
+            &settings,
+            &rules,
+            &mut rng,
+            &mut id_giver,
+        );
+        enc.encounter();
+        assert!(enc.transcript().is_empty());
+
+        let mut enc = Encounter::new(
+            seed1, seed2, &settings, &rules, &mut rng, &mut id_giver,
+        );
+        enc.keep_transcript();
+        enc.encounter();
+        let transcript = enc.transcript();
+        assert!(transcript[0].starts_with("Round 1: [Creature 1]"));
+        let first_thought = "[Creature 1] thinks:\nmate with the target";
+        assert!(transcript.contains(&first_thought.to_owned()));
+    }
+
+    #[test]
+    fn second_fighters_damage_lands_on_the_first() {
+        let rules = Rules::default();
+        let settings = Settings::default();
+        let mut rng = RngState::from_seed(1);
+        let mut id_giver = IDGiver::unthreaded();
+        let p1 = Creature::seed_creature(id_giver.next_creature_id(), &rules);
+        let p2 = Creature::seed_creature(id_giver.next_creature_id(), &rules);
+        let mut enc =
+            Encounter::new(p1, p2, &settings, &rules, &mut rng, &mut id_giver);
+        // Ice doesn't block fire, so p2 always hits and p1 never does
+        enc.p1_action = Defend(Ice);
+        enc.p2_action = Attack(Fire);
+        enc.do_round();
+        assert!(enc.p1.energy() < rules.max_energy);
+        assert_eq!(enc.p2.energy(), rules.max_energy);
+    }
+
+    #[test]
+    fn cheaper_thinker_goes_first() {
+        let rules = Rules::default();
+        let settings = Settings::default();
+        let mut id_giver = IDGiver::unthreaded();
+        let p1 = Creature::seed_creature(id_giver.next_creature_id(), &rules);
+        let p2 = Creature::seed_creature(id_giver.next_creature_id(), &rules);
+        let mut cheap = (*p1.clone().next_decision()).clone();
+        cheap.icount = 1;
+        cheap.skipped = 0;
+        let mut dear = cheap.clone();
+        dear.icount = 5;
+        // Ties are broken at random, so a few seeds make sure this isn't
+        // one
+        for seed in 0..10 {
+            let mut rng = RngState::from_seed(seed);
+            let mut enc = Encounter::new(
+                p1.clone(),
+                p2.clone(),
+                &settings,
+                &rules,
+                &mut rng,
+                &mut id_giver,
+            );
+            enc.keep_transcript();
+            enc.both_decided(&dear, &cheap);
+            let p2_first = format!("{} goes first", enc.p2);
+            assert!(enc.transcript().contains(&p2_first));
+        }
+    }
+
+    fn seeded_arena(filename: &str) -> Arena {
+        let settings = SettingsBuilder::default()
+            .max_population_size(200)
+            .seed(Some(1234))
+            .build()
+            .unwrap();
+        let rules = Rules::default();
+        let mut rng = settings.initial_rng();
+        let population = Creatures::new(
+            settings.max_population_size,
+            rng.spawn(),
+            &rules,
+        );
+        Arena::new(population, rng, filename, settings, rules)
+    }
+
+    fn snapshot(arena: &Arena) -> String {
+        serde_json::to_string(&(&arena.population, &arena.stats, &arena.rng))
+            .unwrap()
+    }
+
+    #[test]
+    fn resuming_from_checkpoint_matches_uninterrupted_run() {
+        let path = env::temp_dir().join("evofighters_resume_test.evo");
+        let filename = path.to_str().unwrap();
+
+        let mut uninterrupted = seeded_arena(filename);
+        for _ in 0..2000 {
+            uninterrupted.step();
+        }
+
+        let mut interrupted = seeded_arena(filename);
+        for _ in 0..1000 {
+            interrupted.step();
+        }
+        interrupted.save().unwrap();
+        let mut resumed =
+            Arena::from_checkpoint(Saver::load(filename).unwrap(), filename);
+        for _ in 0..1000 {
+            resumed.step();
+        }
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(snapshot(&uninterrupted), snapshot(&resumed));
+    }
+
+    /// How long a hundred encounters take between creatures from a
+    /// population that's had time to evolve something more
+    /// interesting than the seed
+    #[bench]
+    fn bench_encounters(b: &mut Bencher) {
+        let path = env::temp_dir().join("evofighters_bench.evo");
+        let mut arena = seeded_arena(path.to_str().unwrap());
+        for _ in 0..20_000 {
+            arena.step();
+        }
+        let fighters: Vec<Creature> =
+            arena.population.iter().take(200).cloned().collect();
+        let mut id_giver = IDGiver::unthreaded();
+        b.iter(|| {
+            let mut rng = RngState::from_seed(1);
+            for pair in fighters.chunks(2) {
+                let mut enc = Encounter::new(
+                    pair[0].clone(),
+                    pair[1].clone(),
+                    &arena.settings,
+                    &arena.rules,
+                    &mut rng,
+                    &mut id_giver,
+                );
+                enc.encounter();
+            }
+        });
+    }
+
+    /// Runs whole events, births and all, so 1000 events per iteration
+    /// makes ns/iter the number of microseconds per event
+    #[bench]
+    fn bench_events(b: &mut Bencher) {
+        let path = env::temp_dir().join("evofighters_bench_events.evo");
+        let mut arena = seeded_arena(path.to_str().unwrap());
+        for _ in 0..20_000 {
+            arena.step();
+        }
+        b.iter(|| {
+            let mut population = arena.population.clone();
+            let mut chronicle = Chronicle::new(0);
+            let mut rng = RngState::from_seed(1);
+            for _ in 0..1000 {
+                run_event(
+                    &mut population,
+                    &mut chronicle,
+                    &mut rng,
+                    &arena.settings,
+                    &arena.rules,
+                );
+            }
+        });
+    }
+}
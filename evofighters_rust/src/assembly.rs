@@ -0,0 +1,478 @@
+//! A readable way to write and read `DNA`, following the grammar in
+//! `EvoFighters/grammar.bnf`. Each word of assembly is one base, so
+//!
+//! ```text
+//! always mate ; always flee
+//! ```
+//!
+//! assembles to the bases `0 2 0 8`. Thoughts are read from DNA one
+//! after another, so a `;` between them is only there to make the
+//! assembly easier to read. `stop` writes a stop codon, which means
+//! nothing and is skipped when the DNA is read, and `#` starts a
+//! comment that runs to the end of the line.
+
+use std::fmt;
+
+use num::FromPrimitive;
+
+use dna::{lex, Gene, DNA};
+use parsing::{
+    Indecision, Parser, PartialTree, Symbol, Thought, TracedBase,
+};
+
+const CONDITIONS: [&str; 8] = [
+    "always",
+    "in_range",
+    "less_than",
+    "greater_than",
+    "equal_to",
+    "not_equal_to",
+    "me_last_act",
+    "target_last_act",
+];
+const VALUES: [&str; 4] = ["literal", "random", "me", "target"];
+const ACTIONS: [&str; 9] = [
+    "subcondition",
+    "attack",
+    "mate",
+    "defend",
+    "use",
+    "signal",
+    "take",
+    "wait",
+    "flee",
+];
+const ATTRIBUTES: [&str; 7] = [
+    "energy",
+    "signal",
+    "generation",
+    "kills",
+    "survived",
+    "num_children",
+    "top_item",
+];
+const DAMAGE_TYPES: [&str; 3] = ["fire", "ice", "electricity"];
+/// Signals start at 1, since 0 is no signal at all
+const SIGNALS: [&str; 7] =
+    ["", "red", "yellow", "blue", "purple", "orange", "green"];
+
+const STOP: &str = "stop";
+
+fn words(symbol: Symbol) -> &'static [&'static str] {
+    match symbol {
+        Symbol::Condition => &CONDITIONS,
+        Symbol::Value => &VALUES,
+        Symbol::Action => &ACTIONS,
+        Symbol::Attribute => &ATTRIBUTES,
+        Symbol::DamageType => &DAMAGE_TYPES,
+        Symbol::Signal => &SIGNALS,
+        Symbol::Literal => &[],
+    }
+}
+
+/// The assembly for `base` read as `symbol`, if it means anything as
+/// one
+pub fn mnemonic(symbol: Symbol, base: i8) -> Option<String> {
+    if base < 0 {
+        return None;
+    }
+    if symbol == Symbol::Literal {
+        return Some(base.to_string());
+    }
+    match words(symbol).get(base as usize) {
+        Some(word) if !word.is_empty() => Some((*word).to_owned()),
+        _ => None,
+    }
+}
+
+/// Turns assembly into DNA
+pub fn assemble(source: &str) -> Result<DNA, String> {
+    let mut assembler = Assembler {
+        words: source
+            .lines()
+            .map(|line| line.split('#').next().unwrap())
+            .flat_map(|line| {
+                line.split(|c: char| c.is_whitespace() || c == ';')
+            })
+            .filter(|word| !word.is_empty())
+            .collect(),
+        position: 0,
+        bases: Vec::new(),
+    };
+    while let Some(&word) = assembler.words.get(assembler.position) {
+        if word == STOP {
+            assembler.position += 1;
+            assembler.bases.push(Gene::STOP_CODON);
+        } else {
+            assembler.condition()?;
+        }
+    }
+    if assembler.bases.is_empty() {
+        return Err("There's nothing to assemble".to_owned());
+    }
+    Ok(DNA::from(assembler.bases))
+}
+
+/// Reads DNA written either as raw bases, like an exported genome, or
+/// as assembly. Anything that's all numbers is raw bases.
+pub fn read_dna(source: &str) -> Result<DNA, String> {
+    let words: Vec<&str> = source.split_whitespace().collect();
+    let is_number = |word: &&str| {
+        let digits = word.strip_prefix('-').unwrap_or(word);
+        !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+    };
+    if words.is_empty() || !words.iter().all(is_number) {
+        return assemble(source);
+    }
+    let bases: Result<Vec<i8>, String> = words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            word.parse().map_err(|_| {
+                format!(
+                    "Base {} is out of range, bases go from {} to {} \
+                     (word {})",
+                    word,
+                    i8::MIN,
+                    i8::MAX,
+                    i + 1
+                )
+            })
+        })
+        .collect();
+    bases.map(DNA::from)
+}
+
+struct Assembler<'a> {
+    words: Vec<&'a str>,
+    position: usize,
+    bases: Vec<i8>,
+}
+
+impl<'a> Assembler<'a> {
+    /// Assembles the next word, which should be a `symbol`
+    fn symbol(&mut self, symbol: Symbol) -> Result<i8, String> {
+        let word = match self.words.get(self.position) {
+            Some(&word) => word,
+            None => {
+                return Err(format!(
+                    "Expected {} but the assembly ended",
+                    symbol.with_article()
+                ))
+            }
+        };
+        self.position += 1;
+        let base = if symbol == Symbol::Literal {
+            word.parse().ok().filter(|&base: &i8| base >= 0)
+        } else {
+            words(symbol)
+                .iter()
+                .position(|&known| !known.is_empty() && known == word)
+                .map(|base| base as i8)
+        };
+        match base {
+            Some(base) => {
+                self.bases.push(base);
+                Ok(base)
+            }
+            None => Err(format!(
+                "Expected {} but found '{}' (word {})",
+                symbol.with_article(),
+                word,
+                self.position
+            )),
+        }
+    }
+
+    fn condition(&mut self) -> Result<(), String> {
+        let base = self.symbol(Symbol::Condition)?;
+        match lex::Condition::from_i8(base).unwrap() {
+            lex::Condition::Always => self.action(),
+            lex::Condition::InRange => {
+                self.value()?;
+                self.value()?;
+                self.value()?;
+                self.action()?;
+                self.action()
+            }
+            lex::Condition::LessThan
+            | lex::Condition::GreaterThan
+            | lex::Condition::EqualTo
+            | lex::Condition::NotEqualTo => {
+                self.value()?;
+                self.value()?;
+                self.action()?;
+                self.action()
+            }
+            lex::Condition::MyLastAction | lex::Condition::OtherLastAction => {
+                self.action()?;
+                self.action()?;
+                self.action()
+            }
+        }
+    }
+
+    fn action(&mut self) -> Result<(), String> {
+        let base = self.symbol(Symbol::Action)?;
+        match lex::Action::from_i8(base).unwrap() {
+            lex::Action::Subcondition => self.condition(),
+            lex::Action::Attack | lex::Action::Defend => {
+                self.symbol(Symbol::DamageType).map(|_| ())
+            }
+            lex::Action::Signal => self.symbol(Symbol::Signal).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    fn value(&mut self) -> Result<(), String> {
+        let base = self.symbol(Symbol::Value)?;
+        match lex::Value::from_i8(base).unwrap() {
+            lex::Value::Literal => self.symbol(Symbol::Literal).map(|_| ()),
+            lex::Value::Me | lex::Value::Other => {
+                self.symbol(Symbol::Attribute).map(|_| ())
+            }
+            lex::Value::Random => Ok(()),
+        }
+    }
+}
+
+/// Every base of some DNA and what it means, thought by thought, in
+/// the order a creature reads them. Reading carries on until a thought
+/// starts where an earlier one did, since from there the creature
+/// just repeats itself.
+pub struct Disassembly<'a> {
+    dna: &'a DNA,
+}
+
+impl<'a> Disassembly<'a> {
+    pub fn new(dna: &'a DNA) -> Disassembly<'a> {
+        Disassembly { dna }
+    }
+
+    fn write_base(
+        f: &mut fmt::Formatter,
+        traced: &TracedBase,
+    ) -> fmt::Result {
+        let meaning = match mnemonic(traced.expected, traced.base) {
+            Some(ref word) if traced.used => word.to_owned(),
+            Some(ref word) => format!("({}, but out of time)", word),
+            _ if traced.base == Gene::STOP_CODON => {
+                "(skipped stop codon)".to_owned()
+            }
+            _ => {
+                format!("(skipped, not {})", traced.expected.with_article())
+            }
+        };
+        writeln!(
+            f,
+            "{:>6} {:>4}  {:indent$}{}",
+            traced.offset,
+            traced.base,
+            "",
+            meaning,
+            indent = traced.depth * 2
+        )
+    }
+}
+
+impl<'a> fmt::Display for Disassembly<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.dna.len() == 0 {
+            return writeln!(f, "There are no bases");
+        }
+        let mut parser = Parser::new(self.dna, 0).traced();
+        let mut starts = Vec::new();
+        loop {
+            let start = parser.current_offset();
+            if let Some(earlier) = starts.iter().position(|&s| s == start) {
+                return writeln!(f, "Then back to thought {}", earlier + 1);
+            }
+            starts.push(start);
+            let thought = parser.next().unwrap();
+            match thought {
+                Thought::Dec(_) => writeln!(
+                    f,
+                    "Thought {} at base {}:",
+                    starts.len(),
+                    start
+                )?,
+                Thought::Ind(ref indecision) => writeln!(
+                    f,
+                    "Thought {} at base {} fails ({}):",
+                    starts.len(),
+                    start,
+                    indecision.reason
+                )?,
+            }
+            let mut last_offset = start;
+            for traced in parser.take_trace() {
+                if traced.offset < last_offset {
+                    writeln!(f, "{:>6}  (wraps around to the start)", "")?;
+                }
+                last_offset = traced.offset;
+                Disassembly::write_base(f, &traced)?;
+            }
+            if let Thought::Ind(Indecision {
+                partial: Some(ref partial),
+                ..
+            }) = thought
+            {
+                writeln!(f, "{:>6}  Got as far as: {}", "", partial)?;
+            }
+        }
+    }
+}
+
+/// The tree as assembly, with whatever the parser didn't get to in
+/// angle brackets
+impl fmt::Display for PartialTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let traced = match self.base {
+            Some(traced) => traced,
+            None => return write!(f, "<{}>", self.symbol),
+        };
+        match mnemonic(self.symbol, traced.base) {
+            Some(word) => write!(f, "{}", word)?,
+            None => write!(f, "{}", traced.base)?,
+        }
+        for child in &self.children {
+            write!(f, " {}", child)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dna::ast;
+
+    fn bases(dna: &DNA) -> Vec<i8> {
+        dna.bases().collect()
+    }
+
+    #[test]
+    fn mnemonics_match_the_lexer() {
+        for (base, _) in CONDITIONS.iter().enumerate() {
+            assert!(lex::Condition::from_usize(base).is_some());
+        }
+        assert!(lex::Condition::from_usize(CONDITIONS.len()).is_none());
+        assert!(lex::Value::from_usize(VALUES.len() - 1).is_some());
+        assert!(lex::Value::from_usize(VALUES.len()).is_none());
+        assert!(lex::Action::from_usize(ACTIONS.len() - 1).is_some());
+        assert!(lex::Action::from_usize(ACTIONS.len()).is_none());
+        assert!(lex::Attribute::from_usize(ATTRIBUTES.len() - 1).is_some());
+        assert!(lex::Attribute::from_usize(ATTRIBUTES.len()).is_none());
+        assert!(lex::DamageType::from_usize(DAMAGE_TYPES.len()).is_none());
+        assert!(lex::Signal::from_usize(0).is_none());
+        assert_eq!(
+            lex::Signal::from_usize(SIGNALS.len() - 1),
+            Some(lex::Signal::Green)
+        );
+        assert_eq!(
+            mnemonic(Symbol::Action, lex::Action::Eat as i8),
+            Some("use".to_owned())
+        );
+    }
+
+    #[test]
+    fn assembles_the_seed() {
+        let dna = assemble("always mate ; stop\nalways flee # the seed")
+            .unwrap();
+        assert_eq!(bases(&dna), bases(&DNA::seed()));
+        assert_eq!(
+            bases(&assemble("always mate ; always flee").unwrap()),
+            vec![0, 2, 0, 8, -1]
+        );
+    }
+
+    #[test]
+    fn assembled_dna_parses_to_what_was_written() {
+        let dna = assemble(
+            "less_than me energy literal 5 \
+             subcondition target_last_act attack fire flee defend fire \
+             signal red",
+        ).unwrap();
+        let tree = Parser::new(&dna, 0)
+            .next()
+            .unwrap()
+            .into_result()
+            .ok()
+            .unwrap()
+            .tree;
+        assert_eq!(
+            tree,
+            ast::Condition::BinCompare {
+                operation: ast::BinOp::LT,
+                lhs: ast::Value::Me(lex::Attribute::Energy),
+                rhs: ast::Value::Literal(5),
+                affirmed: ast::Action::Subcondition(Box::new(
+                    ast::Condition::ActionCompare {
+                        actor_type: ast::ActorType::Other,
+                        action: ast::Action::Attack(lex::DamageType::Fire),
+                        affirmed: ast::Action::Flee,
+                        denied: ast::Action::Defend(lex::DamageType::Fire),
+                    }
+                )),
+                denied: ast::Action::Signal(lex::Signal::Red),
+            }
+        );
+    }
+
+    #[test]
+    fn assembly_errors_say_what_was_expected() {
+        assert_eq!(
+            assemble("always dance").err().unwrap(),
+            "Expected an action but found 'dance' (word 2)"
+        );
+        assert_eq!(
+            assemble("equal_to random").err().unwrap(),
+            "Expected a value but the assembly ended"
+        );
+        assert!(assemble("# nothing").is_err());
+    }
+
+    #[test]
+    fn disassembly_shows_skipped_bases_and_wrapping() {
+        let dna = DNA::from(vec![0, 2, -1, 0, 9, 8]);
+        assert_eq!(
+            Disassembly::new(&dna).to_string(),
+            "Thought 1 at base 0:\n\
+             \x20    0    0  always\n\
+             \x20    1    2  mate\n\
+             Thought 2 at base 2:\n\
+             \x20    2   -1  (skipped stop codon)\n\
+             \x20    3    0  always\n\
+             \x20    4    9  (skipped, not an action)\n\
+             \x20    5    8  flee\n\
+             Thought 3 at base 6:\n\
+             \x20    6   -1  (skipped stop codon)\n\
+             \x20    7   -1  (skipped stop codon)\n\
+             \x20    8   -1  (skipped stop codon)\n\
+             \x20    9   -1  (skipped stop codon)\n\
+             \x20       (wraps around to the start)\n\
+             \x20    0    0  always\n\
+             \x20    1    2  mate\n\
+             Then back to thought 2\n"
+        );
+    }
+
+    #[test]
+    fn disassembly_shows_how_far_failing_thoughts_got() {
+        let mut bases = vec![2, 2, 3];
+        bases.extend(vec![9; 120]);
+        let listing = Disassembly::new(&DNA::from(bases)).to_string();
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(
+            lines[0],
+            "Thought 1 at base 0 fails (took too long, still looking for \
+             a value at base 100):"
+        );
+        assert_eq!(lines[4], "     3    9  (skipped, not a value)");
+        assert_eq!(
+            lines[102],
+            "        Got as far as: less_than me kills <value> <action> \
+             <action>"
+        );
+    }
+}
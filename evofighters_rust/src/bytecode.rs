@@ -0,0 +1,409 @@
+//! Thought trees lowered to a flat list of instructions. A decision is
+//! compiled the first time it's run in a fight (see
+//! `Decision::program`), and then run every round of every fight it's
+//! in after that, so running it has to be cheap.
+//! Walking the boxed tree chases a pointer for every subcondition,
+//! while a program is one contiguous array that's run in a loop.
+//!
+//! Each branch of a condition ends with a `Do`, so there's never any
+//! need to jump past the denied branch once the affirmed one is done.
+//! A condition is laid out as the test, then the affirmed branch, then
+//! the denied branch, which the test jumps to when it fails.
+
+use std::cmp::{max, min};
+
+use creatures::Creature;
+use dna::{ast, Gene};
+use eval::PerformableAction;
+use rng::RngState;
+
+/// Where an instruction is in its program
+type Address = u16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Op {
+    /// Stop and perform this action
+    Do(PerformableAction),
+    /// Go on to the next instruction if `value` is between the bounds,
+    /// otherwise jump to `denied`
+    InRange {
+        value: ast::Value,
+        bound_a: ast::Value,
+        bound_b: ast::Value,
+        denied: Address,
+    },
+    /// Go on to the next instruction if the comparison holds,
+    /// otherwise jump to `denied`
+    Compare {
+        operation: ast::BinOp,
+        lhs: ast::Value,
+        rhs: ast::Value,
+        denied: Address,
+    },
+    /// Runs the code at `action`, and goes on to the next instruction
+    /// if the action it decides on was the actor's last action,
+    /// otherwise jumps to `denied`. The action's code is after both
+    /// branches.
+    LastAction {
+        actor_type: ast::ActorType,
+        action: Address,
+        denied: Address,
+    },
+}
+
+/// A compiled `ast::Condition`, which decides on the same action as
+/// `eval::evaluate` would for the tree it was compiled from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    ops: Vec<Op>,
+}
+
+impl Program {
+    pub fn compile(tree: &ast::Condition) -> Program {
+        let mut program = Program { ops: Vec::new() };
+        program.lower_condition(tree);
+        program
+    }
+
+    pub fn run(&self, me: &Creature, other: &Creature) -> PerformableAction {
+        self.run_from(0, me, other, &mut None)
+    }
+
+    /// Every `Random` value in a decision is the same number, since
+    /// it's drawn from a fresh rng seeded by the two creatures, so
+    /// it's only worked out the first time it's needed
+    fn run_from(
+        &self,
+        mut address: usize,
+        me: &Creature,
+        other: &Creature,
+        random: &mut Option<usize>,
+    ) -> PerformableAction {
+        loop {
+            let affirmed = match self.ops[address] {
+                Op::Do(action) => return action,
+                Op::InRange {
+                    value,
+                    bound_a,
+                    bound_b,
+                    denied,
+                } => {
+                    let a = eval_value(me, other, bound_a, random);
+                    let b = eval_value(me, other, bound_b, random);
+                    let check_val = eval_value(me, other, value, random);
+                    if min(a, b) <= check_val && check_val <= max(a, b) {
+                        None
+                    } else {
+                        Some(denied)
+                    }
+                }
+                Op::Compare {
+                    operation,
+                    lhs,
+                    rhs,
+                    denied,
+                } => {
+                    let lhs = eval_value(me, other, lhs, random);
+                    let rhs = eval_value(me, other, rhs, random);
+                    let holds = match operation {
+                        ast::BinOp::LT => lhs < rhs,
+                        ast::BinOp::GT => lhs > rhs,
+                        ast::BinOp::EQ => lhs == rhs,
+                        ast::BinOp::NE => lhs != rhs,
+                    };
+                    if holds {
+                        None
+                    } else {
+                        Some(denied)
+                    }
+                }
+                Op::LastAction {
+                    actor_type,
+                    action,
+                    denied,
+                } => {
+                    let actor = match actor_type {
+                        ast::ActorType::Me => me,
+                        ast::ActorType::Other => other,
+                    };
+                    let action =
+                        self.run_from(action as usize, me, other, random);
+                    if action == actor.last_action {
+                        None
+                    } else {
+                        Some(denied)
+                    }
+                }
+            };
+            address = match affirmed {
+                None => address + 1,
+                Some(denied) => denied as usize,
+            };
+        }
+    }
+
+    fn lower_condition(&mut self, cond: &ast::Condition) {
+        match *cond {
+            ast::Condition::Always(ref action) => self.lower_action(action),
+            ast::Condition::RangeCompare {
+                value,
+                bound_a,
+                bound_b,
+                ref affirmed,
+                ref denied,
+            } => {
+                let test = self.emit(Op::InRange {
+                    value,
+                    bound_a,
+                    bound_b,
+                    denied: 0,
+                });
+                self.lower_branches(test, affirmed, denied);
+            }
+            ast::Condition::BinCompare {
+                operation,
+                lhs,
+                rhs,
+                ref affirmed,
+                ref denied,
+            } => {
+                let test = self.emit(Op::Compare {
+                    operation,
+                    lhs,
+                    rhs,
+                    denied: 0,
+                });
+                self.lower_branches(test, affirmed, denied);
+            }
+            ast::Condition::ActionCompare {
+                actor_type,
+                ref action,
+                ref affirmed,
+                ref denied,
+            } => {
+                let test = self.emit(Op::LastAction {
+                    actor_type,
+                    action: 0,
+                    denied: 0,
+                });
+                self.lower_branches(test, affirmed, denied);
+                let action_start = self.next_address();
+                self.lower_action(action);
+                if let Op::LastAction { ref mut action, .. } = self.ops[test]
+                {
+                    *action = action_start;
+                }
+            }
+        }
+    }
+
+    /// Lays out both branches after the test at `test`, and points
+    /// the test at the denied one
+    fn lower_branches(
+        &mut self,
+        test: usize,
+        affirmed: &ast::Action,
+        denied: &ast::Action,
+    ) {
+        self.lower_action(affirmed);
+        let denied_start = self.next_address();
+        self.lower_action(denied);
+        match self.ops[test] {
+            Op::InRange { ref mut denied, .. }
+            | Op::Compare { ref mut denied, .. }
+            | Op::LastAction { ref mut denied, .. } => *denied = denied_start,
+            Op::Do(_) => unreachable!("Only tests have a denied branch"),
+        }
+    }
+
+    fn lower_action(&mut self, action: &ast::Action) {
+        let performable = match *action {
+            ast::Action::Subcondition(ref sub) => {
+                self.lower_condition(sub);
+                return;
+            }
+            ast::Action::Attack(dmg) => PerformableAction::Attack(dmg),
+            ast::Action::Defend(dmg) => PerformableAction::Defend(dmg),
+            ast::Action::Signal(sig) => PerformableAction::Signal(sig),
+            ast::Action::Eat => PerformableAction::Eat,
+            ast::Action::Take => PerformableAction::Take,
+            ast::Action::Wait => PerformableAction::Wait,
+            ast::Action::Flee => PerformableAction::Flee,
+            ast::Action::Mate => PerformableAction::Mate,
+        };
+        self.emit(Op::Do(performable));
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        self.ops.push(op);
+        self.ops.len() - 1
+    }
+
+    /// Trees are limited by how long a creature may think, so their
+    /// programs are always far too short to overflow an `Address`
+    fn next_address(&self) -> Address {
+        self.ops.len() as Address
+    }
+}
+
+fn eval_value(
+    me: &Creature,
+    other: &Creature,
+    val: ast::Value,
+    random: &mut Option<usize>,
+) -> usize {
+    match val {
+        ast::Value::Literal(x) => x as usize,
+        ast::Value::Random => *random.get_or_insert_with(|| {
+            RngState::from_creatures(me, other)
+                .rand_range(0, Gene::MAX_MEANINGFUL_VALUE as usize)
+        }),
+        ast::Value::Me(attr) => me.attr(attr),
+        ast::Value::Other(attr) => other.attr(attr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::Bencher;
+
+    use creatures::IDGiver;
+    use dna::lex::{DamageType, Signal};
+    use dna::DNA;
+    use eval;
+    use parsing::{Decision, Parser};
+    use rules::Rules;
+
+    /// Parses whatever decisions there are in a random strand of DNA.
+    /// Unlike evolved DNA, most of it is nonsense, which makes for
+    /// big, deep trees.
+    fn random_decisions(rng: &mut RngState) -> Vec<Decision> {
+        let bases: Vec<i8> = (0..200)
+            .map(|_| {
+                rng.rand_range(
+                    Gene::STOP_CODON,
+                    Gene::MAX_MEANINGFUL_VALUE + 1,
+                )
+            })
+            .collect();
+        Parser::new(&DNA::from(bases), 0)
+            .take(10)
+            .filter_map(|thought| thought.into_result().ok())
+            .collect()
+    }
+
+    /// A creature with random values for everything a decision can
+    /// look at, kept small so comparisons go both ways
+    fn random_creature(
+        id_giver: &mut IDGiver,
+        rng: &mut RngState,
+    ) -> Creature {
+        let last_actions = [
+            PerformableAction::Attack(DamageType::Fire),
+            PerformableAction::Defend(DamageType::Ice),
+            PerformableAction::Signal(Signal::Red),
+            PerformableAction::Eat,
+            PerformableAction::Mate,
+            PerformableAction::Wait,
+            PerformableAction::NoAction,
+        ];
+        let mut creature = Creature::seed_creature(
+            id_giver.next_creature_id(),
+            &Rules::default(),
+        );
+        creature.generation = rng.rand_range(0, 10);
+        creature.stats.kills = rng.rand_range(0, 10);
+        creature.stats.survived = rng.rand_range(0, 10);
+        creature.stats.num_children = rng.rand_range(0, 10);
+        creature.last_action =
+            last_actions[rng.rand_range(0, last_actions.len())];
+        creature.signal = if rng.rand() {
+            Some(Signal::Blue)
+        } else {
+            None
+        };
+        creature
+    }
+
+    #[test]
+    fn programs_decide_the_same_as_their_trees() {
+        let mut rng = RngState::from_seed(21);
+        let mut id_giver = IDGiver::unthreaded();
+        let mut decisions = 0;
+        for _ in 0..500 {
+            let me = random_creature(&mut id_giver, &mut rng);
+            let other = random_creature(&mut id_giver, &mut rng);
+            for decision in random_decisions(&mut rng) {
+                let program = Program::compile(&decision.tree);
+                assert_eq!(
+                    program.run(&me, &other),
+                    eval::evaluate(&me, &other, &decision.tree),
+                    "Disagreed about:\n{}\n{:?}",
+                    decision.tree,
+                    program
+                );
+                decisions += 1;
+            }
+        }
+        assert!(decisions > 1000);
+    }
+
+    #[test]
+    fn branches_are_laid_out_after_their_test() {
+        let tree = ast::Condition::ActionCompare {
+            actor_type: ast::ActorType::Other,
+            action: ast::Action::Flee,
+            affirmed: ast::Action::Mate,
+            denied: ast::Action::Eat,
+        };
+        assert_eq!(
+            Program::compile(&tree).ops,
+            vec![
+                Op::LastAction {
+                    actor_type: ast::ActorType::Other,
+                    action: 3,
+                    denied: 2,
+                },
+                Op::Do(PerformableAction::Mate),
+                Op::Do(PerformableAction::Eat),
+                Op::Do(PerformableAction::Flee),
+            ]
+        );
+    }
+
+    fn bench_decisions() -> (Creature, Creature, Vec<Decision>) {
+        let mut rng = RngState::from_seed(1);
+        let mut id_giver = IDGiver::unthreaded();
+        let me = random_creature(&mut id_giver, &mut rng);
+        let other = random_creature(&mut id_giver, &mut rng);
+        let decisions = (0..100)
+            .flat_map(|_| random_decisions(&mut rng))
+            .collect();
+        (me, other, decisions)
+    }
+
+    #[bench]
+    fn bench_walking_trees(b: &mut Bencher) {
+        let (me, other, decisions) = bench_decisions();
+        b.iter(|| {
+            for decision in &decisions {
+                eval::evaluate(&me, &other, &decision.tree);
+            }
+        });
+    }
+
+    #[bench]
+    fn bench_running_programs(b: &mut Bencher) {
+        let (me, other, decisions) = bench_decisions();
+        let programs: Vec<Program> = decisions
+            .iter()
+            .map(|decision| Program::compile(&decision.tree))
+            .collect();
+        b.iter(|| {
+            for program in &programs {
+                program.run(&me, &other);
+            }
+        });
+    }
+}
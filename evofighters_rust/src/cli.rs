@@ -1,8 +1,35 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::process;
+use std::str::FromStr;
+use std::sync::Arc;
+
 use clap;
+use arena::{Arena, Encounter};
+use assembly;
+use assembly::Disassembly;
+use creatures::{Creature, CreatureID};
+use diversity;
+use dashboard::Dashboard;
 use dna;
+use hall_of_fame::Champion;
+use lineage::{Lineage, LineageLog};
+use metrics::{MetricsFormat, MetricsLog};
+use rules::Rules;
+use shell::Shell;
+use serde_json;
 use sim;
+use tui::Tui;
+use stats::Ranking;
+use tournament;
+use tournament::Tournament;
 use simplify;
-use saver::SettingsBuilder;
+use rng::RngState;
+use saver::{OwnedCheckpoint, Saver, Settings, SettingsBuilder,
+            SettingsOverrides};
+use seed_dna::SeedDNA;
 
 pub fn parse_args() -> clap::ArgMatches<'static> {
     clap::App::new(
@@ -52,13 +79,357 @@ pub fn parse_args() -> clap::ArgMatches<'static> {
                 .takes_value(true)
                 .global(true),
         )
+        .arg(
+            clap::Arg::with_name("type_effectiveness")
+                .long("type-effectiveness")
+                .help(
+                    "Make fire beat ice, ice beat electricity, and \
+                     electricity beat fire",
+                )
+                .global(true),
+        )
+        .arg(
+            clap::Arg::with_name("migration_rate")
+                .long("migration-rate")
+                .value_name("MIGRATION_RATE")
+                .help("Fraction of each island's creatures that migrate")
+                .takes_value(true)
+                .validator(fraction)
+                .global(true),
+        )
+        .arg(
+            clap::Arg::with_name("migration_interval")
+                .long("migration-interval")
+                .value_name("EVENTS")
+                .help("Number of events on each island between migrations")
+                .takes_value(true)
+                .validator(positive)
+                .global(true),
+        )
+        .arg(
+            clap::Arg::with_name("migration_topology")
+                .long("migration-topology")
+                .value_name("TOPOLOGY")
+                .help("Which islands migrants move to")
+                .possible_values(&["ring", "full", "random"])
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            clap::Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed for the random number generator")
+                .validator(parses_as::<u64>)
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            clap::Arg::with_name("backups")
+                .long("backups")
+                .value_name("N")
+                .help(
+                    "Number of previous checkpoints to keep as \
+                     SAVEFILE.1 to SAVEFILE.N",
+                )
+                .takes_value(true)
+                .validator(parses_as::<usize>)
+                .global(true),
+        )
+        .arg(
+            clap::Arg::with_name("encoding")
+                .long("encoding")
+                .value_name("ENCODING")
+                .help(
+                    "How to write checkpoints. Binary is much faster to \
+                     save for large populations, JSON is easier to inspect.",
+                )
+                .possible_values(&["json", "binary"])
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            clap::Arg::with_name("rules")
+                .long("rules")
+                .value_name("RULES_FILE")
+                .help(
+                    "JSON file with the combat rules for a new population \
+                     (defaults to the built in rules)",
+                )
+                .takes_value(true)
+                .global(true),
+        )
         .subcommand(
             clap::SubCommand::with_name("simulate")
-                .about("Main command. Runs an evofighters simulation"),
+                .about("Main command. Runs an evofighters simulation")
+                .arg(
+                    clap::Arg::with_name("threads")
+                        .short("t")
+                        .long("threads")
+                        .value_name("THREADS")
+                        .help(
+                            "Number of worker threads to run \
+                             (defaults to the number of physical cores)",
+                        )
+                        .validator(positive)
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("metrics")
+                        .long("metrics")
+                        .value_name("FORMAT")
+                        .possible_values(&["jsonl", "csv"])
+                        .help(
+                            "Log metrics to SAVEFILE.metrics.FORMAT as \
+                             JSON Lines or CSV",
+                        )
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("metrics_file")
+                        .long("metrics-file")
+                        .value_name("FILE")
+                        .help("Log metrics to this file instead")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("http")
+                        .long("http")
+                        .value_name("PORT")
+                        .help(
+                            "Serve a dashboard on localhost at this port, \
+                             with JSON stats, Prometheus metrics and \
+                             buttons to save and pause",
+                        )
+                        .validator(parses_as::<u16>)
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("seed_dna")
+                        .long("seed-dna")
+                        .value_name("FILE")
+                        .help(
+                            "Start a new population with the genomes in \
+                             this file, one per line as bases or assembly, \
+                             each optionally prefixed with 'SHARE:'",
+                        )
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("lineage")
+                        .long("lineage")
+                        .overrides_with("no_lineage")
+                        .help(
+                            "Log every birth to SAVEFILE.lineage for \
+                             export-tree (the default). Each birth takes \
+                             40 bytes, which comes to a few GB an hour on \
+                             four threads",
+                        ),
+                )
+                .arg(
+                    clap::Arg::with_name("no_lineage")
+                        .long("no-lineage")
+                        .overrides_with("lineage")
+                        .help("Don't log births, export-tree won't work"),
+                )
+                .arg(
+                    clap::Arg::with_name("tui")
+                        .long("tui")
+                        .help(
+                            "Show a full-screen dashboard with the best \
+                             creatures instead of the status line",
+                        ),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("shell").about(
+                "Interactive shell to inspect and tinker with a population",
+            ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("show")
+                .about("Shows a creature from the save file and its strategy")
+                .arg(
+                    clap::Arg::with_name("creature_id")
+                        .required(true)
+                        .value_name("CREATURE_ID"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("fight")
+                .about(
+                    "Replays an encounter between two creatures from the \
+                     save file without changing it. Use --seed to fix \
+                     the outcome, and 0 as an id to fight a feeder",
+                )
+                .arg(
+                    clap::Arg::with_name("creature_ids")
+                        .required(true)
+                        .number_of_values(2)
+                        .value_name("CREATURE_ID"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("tournament")
+                .about(
+                    "Runs a round robin tournament between the best \
+                     creatures in the save file, or the ones given",
+                )
+                .arg(
+                    clap::Arg::with_name("top")
+                        .short("k")
+                        .long("top")
+                        .value_name("K")
+                        .default_value("8")
+                        .help("How many of the best creatures to enter")
+                        .validator(positive)
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("by")
+                        .long("by")
+                        .value_name("RANKING")
+                        .possible_values(&["kills", "children", "survived"])
+                        .default_value("kills")
+                        .help("What makes a creature one of the best")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("bouts")
+                        .short("n")
+                        .long("bouts")
+                        .value_name("BOUTS")
+                        .default_value("20")
+                        .help("How many times each pair of creatures fights")
+                        .validator(positive)
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("creature_ids")
+                        .multiple(true)
+                        .value_name("CREATURE_ID")
+                        .help("Enter these creatures instead of the best"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("hall-of-fame")
+                .about("Lists or exports the most notable dead creatures")
+                .arg(
+                    clap::Arg::with_name("by")
+                        .long("by")
+                        .value_name("RANKING")
+                        .possible_values(&["kills", "children", "survived"])
+                        .help("Only list the champions for one ranking")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("export")
+                        .long("export")
+                        .value_name("FILE")
+                        .help("Write every champion to a file")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["json", "dna"])
+                        .default_value("json")
+                        .help(
+                            "Export as JSON, or as DNA with one genome \
+                             per line",
+                        )
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("diversity")
+                .about(
+                    "Reports the genetic diversity and species of the \
+                     population in the save file",
+                )
+                .arg(
+                    clap::Arg::with_name("species")
+                        .short("n")
+                        .long("species")
+                        .value_name("N")
+                        .default_value("10")
+                        .help("How many of the biggest species to list")
+                        .validator(parses_as::<usize>)
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("export-tree")
+                .about(
+                    "Exports the family tree of the given creatures, or of \
+                     the whole population",
+                )
+                .arg(
+                    clap::Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["dot", "newick"])
+                        .default_value("dot")
+                        .help(
+                            "Graphviz DOT with both parents, or Newick \
+                             following first parents",
+                        )
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write the tree here instead of to stdout")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("creature_ids")
+                        .multiple(true)
+                        .value_name("CREATURE_ID")
+                        .help("Only export the ancestry of these creatures"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("assemble")
+                .about(
+                    "Turns DNA assembly, like 'always mate ; always flee', \
+                     into bases",
+                )
+                .arg(
+                    clap::Arg::with_name("source")
+                        .value_name("FILE")
+                        .help("Read the assembly from here instead of stdin"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("disassemble")
+                .about(
+                    "Shows what each of the given bases means, in the order \
+                     a creature reads them",
+                )
+                .setting(clap::AppSettings::AllowNegativeNumbers)
+                .arg(
+                    clap::Arg::with_name("bases")
+                        .required(true)
+                        .multiple(true)
+                        .value_name("BASE"),
+                ),
         )
         .subcommand(
             clap::SubCommand::with_name("cycle-check")
                 .about("Does a cycle detection on the given bases")
+                .setting(clap::AppSettings::AllowNegativeNumbers)
+                .arg(
+                    clap::Arg::with_name("trace")
+                        .long("trace")
+                        .help(
+                            "Show every base read, what it was read as, \
+                             and how far failing thoughts got",
+                        ),
+                )
                 .arg(
                     clap::Arg::with_name("bases")
                         .required(true)
@@ -69,17 +440,76 @@ pub fn parse_args() -> clap::ArgMatches<'static> {
         .get_matches()
 }
 
+/// Lets clap reject an argument that isn't a valid `T` with a usage
+/// message, instead of it panicking when it's parsed
+fn parses_as<T: FromStr>(value: String) -> Result<(), String>
+where
+    T::Err: fmt::Display,
+{
+    value.parse::<T>().map(|_| ()).map_err(|err| err.to_string())
+}
+
+/// Lets clap reject anything but a whole number above zero
+fn positive(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Lets clap reject anything but a number from 0 to 1
+fn fraction(value: String) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(x) if (0.0..=1.0).contains(&x) => Ok(()),
+        Ok(_) => Err("must be from 0 to 1".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 pub fn execute_command(app: &clap::ArgMatches) {
     match app.subcommand() {
         ("cycle-check", Some(check)) => {
-            cycle_check(check.values_of("bases").unwrap())
+            cycle_check(
+                check.values_of("bases").unwrap(),
+                check.is_present("trace"),
+            )
+        }
+        ("assemble", Some(assemble_args)) => {
+            assemble(assemble_args.value_of("source"))
+        }
+        ("disassemble", Some(disassemble_args)) => {
+            disassemble(disassemble_args.values_of("bases").unwrap())
+        }
+        ("shell", Some(_)) => run_shell(app),
+        ("fight", Some(fight_args)) => fight(
+            app.value_of("savefile").unwrap(),
+            fight_args.values_of("creature_ids").unwrap(),
+            app.value_of("seed"),
+        ),
+        ("tournament", Some(tournament_args)) => {
+            tournament(app, tournament_args)
         }
+        ("hall-of-fame", Some(hof_args)) => hall_of_fame(
+            app.value_of("savefile").unwrap(),
+            hof_args,
+        ),
+        ("diversity", Some(diversity_args)) => diversity(
+            app.value_of("savefile").unwrap(),
+            diversity_args.value_of("species").unwrap(),
+        ),
+        ("export-tree", Some(tree_args)) => {
+            export_tree(app.value_of("savefile").unwrap(), tree_args)
+        }
+        ("show", Some(show_args)) => show(
+            app.value_of("savefile").unwrap(),
+            show_args.value_of("creature_id").unwrap(),
+        ),
         _ => run_simulation(app),
     }
 }
 
-pub fn run_simulation(app: &clap::ArgMatches) {
-    let filename = app.value_of("savefile").unwrap();
+fn settings_from_args(app: &clap::ArgMatches) -> Settings {
     let mut sb = SettingsBuilder::default();
     if let Some(mr) = app.value_of("mutation_rate") {
         sb.mutation_rate(mr.parse().unwrap());
@@ -90,18 +520,416 @@ pub fn run_simulation(app: &clap::ArgMatches) {
     if let Some(metric_fps) = app.value_of("metric_fps") {
         sb.metric_fps(metric_fps.parse().unwrap());
     }
-    let settings = sb.build().unwrap();
-    sim::Simulation::new(filename, settings).simulate();
+    if app.is_present("type_effectiveness") {
+        sb.type_effectiveness(true);
+    }
+    if let Some(rate) = app.value_of("migration_rate") {
+        sb.migration_rate(rate.parse().unwrap());
+    }
+    if let Some(interval) = app.value_of("migration_interval") {
+        sb.migration_interval(interval.parse().unwrap());
+    }
+    if let Some(topology) = app.value_of("migration_topology") {
+        sb.migration_topology(topology.parse().unwrap());
+    }
+    if let Some(seed) = app.value_of("seed") {
+        sb.seed(Some(seed.parse().unwrap()));
+    }
+    if let Some(backups) = app.value_of("backups") {
+        sb.backups(backups.parse().unwrap());
+    }
+    if let Some(encoding) = app.value_of("encoding") {
+        sb.checkpoint_encoding(encoding.parse().unwrap());
+    }
+    sb.build().unwrap()
+}
+
+/// The settings from `settings_from_args` that were given explicitly,
+/// and so should win over the ones saved with a population
+fn overrides_from_args(app: &clap::ArgMatches) -> SettingsOverrides {
+    SettingsOverrides {
+        migration_rate: app
+            .value_of("migration_rate")
+            .map(|rate| rate.parse().unwrap()),
+        migration_interval: app
+            .value_of("migration_interval")
+            .map(|interval| interval.parse().unwrap()),
+        migration_topology: app
+            .value_of("migration_topology")
+            .map(|topology| topology.parse().unwrap()),
+        backups: app.value_of("backups").map(|n| n.parse().unwrap()),
+        checkpoint_encoding: app
+            .value_of("encoding")
+            .map(|encoding| encoding.parse().unwrap()),
+    }
+}
+
+fn rules_from_args(app: &clap::ArgMatches) -> Rules {
+    match app.value_of("rules") {
+        Some(rules_file) => Rules::load(rules_file).unwrap_or_else(|err| {
+            eprintln!("Couldn't load rules from {}: {}", rules_file, err);
+            process::exit(1)
+        }),
+        None => Rules::default(),
+    }
+}
+
+pub fn run_simulation(app: &clap::ArgMatches) {
+    let filename = app.value_of("savefile").unwrap();
+    let settings = settings_from_args(app);
+    let rules = rules_from_args(app);
+    let sim_args = app.subcommand_matches("simulate");
+    let num_threads = sim_args
+        .and_then(|sim| sim.value_of("threads"))
+        .map(|threads| threads.parse().unwrap());
+    let mut simulation =
+        sim::Simulation::new(filename, settings, rules, num_threads);
+    simulation.override_saved_settings(overrides_from_args(app));
+    if let Some(log) = sim_args.and_then(|sim| metrics_log(filename, sim)) {
+        simulation.log_metrics(log);
+    }
+    if sim_args.is_some_and(|sim| sim.is_present("no_lineage")) {
+        simulation.log_lineage(false);
+    }
+    if let Some(seed_file) = sim_args.and_then(|sim| sim.value_of("seed_dna"))
+    {
+        let seed_dna = SeedDNA::load(seed_file).unwrap_or_else(|err| {
+            eprintln!("Couldn't load seed DNA from {}: {}", seed_file, err);
+            process::exit(1)
+        });
+        simulation.seed_with(seed_dna);
+    }
+    let dashboard = Arc::new(Dashboard::new());
+    let http = sim_args.and_then(|sim| sim.value_of("http"));
+    let use_tui = sim_args.is_some_and(|sim| sim.is_present("tui"));
+    if let Some(port) = http {
+        let port = port.parse().unwrap();
+        if let Err(err) = Dashboard::serve(Arc::clone(&dashboard), port) {
+            eprintln!("Couldn't serve the dashboard on port {}: {}", port, err);
+            process::exit(1)
+        }
+        println!("Dashboard at http://127.0.0.1:{}/", port);
+    }
+    if http.is_some() || use_tui {
+        simulation.attach_dashboard(Arc::clone(&dashboard));
+    }
+    if !use_tui {
+        return simulation
+            .simulate()
+            .unwrap_or_else(|err| exit_unloadable(filename, &err));
+    }
+    let tui = Tui::start(Arc::clone(&dashboard)).unwrap_or_else(|err| {
+        eprintln!("Couldn't take over the terminal: {}", err);
+        process::exit(1)
+    });
+    let result = simulation.simulate();
+    if let Err(err) = tui.stop() {
+        eprintln!("The terminal UI failed: {}", err);
+    }
+    // So how the run ended is still on screen
+    for message in dashboard.messages(Tui::FINAL_MESSAGES) {
+        println!("{}", message);
+    }
+    if let Err(err) = result {
+        exit_unloadable(filename, &err);
+    }
+}
+
+/// Refuses to go on with a save file that exists but can't be
+/// loaded, rather than starting a new population over it
+fn exit_unloadable(filename: &str, err: &io::Error) -> ! {
+    eprintln!("Couldn't load {}: {}", filename, err);
+    eprintln!(
+        "Move it out of the way to start a new population, or load one \
+         of its backups with --file"
+    );
+    process::exit(1)
+}
+
+/// Opens the metrics log asked for by the simulate arguments, if any
+fn metrics_log(filename: &str, args: &clap::ArgMatches) -> Option<MetricsLog> {
+    if !args.is_present("metrics") && !args.is_present("metrics_file") {
+        return None;
+    }
+    let format = match args.value_of("metrics") {
+        Some(format) => format.parse().unwrap(),
+        None => MetricsFormat::JsonLines,
+    };
+    let metrics_file = match args.value_of("metrics_file") {
+        Some(metrics_file) => metrics_file.to_owned(),
+        None => MetricsLog::default_filename(filename, format),
+    };
+    match MetricsLog::open(&metrics_file, format) {
+        Ok(log) => Some(log),
+        Err(err) => {
+            eprintln!("Couldn't open {}: {}", metrics_file, err);
+            process::exit(1)
+        }
+    }
+}
+
+pub fn run_shell(app: &clap::ArgMatches) {
+    let filename = app.value_of("savefile").unwrap();
+    let settings = settings_from_args(app);
+    let rules = rules_from_args(app);
+    let mut simulation =
+        sim::Simulation::new(filename, settings, rules, Some(1));
+    simulation.override_saved_settings(overrides_from_args(app));
+    let checkpoint = simulation
+        .load_or_create()
+        .unwrap_or_else(|err| exit_unloadable(filename, &err));
+    Shell::new(Arena::from_checkpoint(checkpoint, filename), filename).run();
+}
+
+fn parse_creature_id(creature_id: &str) -> CreatureID {
+    creature_id.parse().unwrap_or_else(|_| {
+        eprintln!("'{}' isn't a valid creature id", creature_id);
+        process::exit(1)
+    })
+}
+
+fn load_checkpoint(filename: &str) -> OwnedCheckpoint {
+    Saver::load(filename).unwrap_or_else(|err| {
+        eprintln!("Couldn't load {}: {}", filename, err);
+        process::exit(1)
+    })
+}
+
+fn find_creature(
+    checkpoint: &OwnedCheckpoint,
+    id: CreatureID,
+    filename: &str,
+) -> Creature {
+    if id.is_feeder() {
+        return Creature::feeder();
+    }
+    match checkpoint.creatures.get(id) {
+        Some(creature) => creature.clone(),
+        None => {
+            eprintln!("There's no creature {} in {}", id, filename);
+            process::exit(1)
+        }
+    }
+}
+
+pub fn show(filename: &str, creature_id: &str) {
+    let id = parse_creature_id(creature_id);
+    let checkpoint = load_checkpoint(filename);
+    print!("{}", find_creature(&checkpoint, id, filename).details());
 }
 
-pub fn cycle_check(bases: clap::Values) {
-    let dna_args: dna::DNA = dna::DNA::from(
+pub fn fight(filename: &str, mut ids: clap::Values, seed: Option<&str>) {
+    let id1 = parse_creature_id(ids.next().unwrap());
+    let id2 = parse_creature_id(ids.next().unwrap());
+    if id1 == id2 {
+        eprintln!("A creature can't fight itself");
+        process::exit(1)
+    }
+    let mut checkpoint = load_checkpoint(filename);
+    let p1 = find_creature(&checkpoint, id1, filename);
+    let p2 = find_creature(&checkpoint, id2, filename);
+    let mut rng = match seed {
+        Some(seed) => RngState::from_seed(seed.parse().unwrap()),
+        None => checkpoint.rng.clone(),
+    };
+    let mut id_giver = *checkpoint.creatures.id_giver();
+    let mut enc = Encounter::new(
+        p1,
+        p2,
+        &checkpoint.settings,
+        &checkpoint.rules,
+        &mut rng,
+        &mut id_giver,
+    );
+    enc.keep_transcript();
+    enc.encounter();
+    for line in enc.transcript() {
+        println!("{}", line);
+    }
+    println!();
+    for fighter in &[&enc.p1, &enc.p2] {
+        if fighter.is_feeder() {
+            continue;
+        } else if fighter.alive() {
+            println!("{} ends with {} energy", fighter, fighter.energy());
+        } else {
+            println!("{} is dead", fighter);
+        }
+    }
+    for child in &enc.children {
+        println!("{} was born", child);
+    }
+}
+
+pub fn tournament(app: &clap::ArgMatches, args: &clap::ArgMatches) {
+    let filename = app.value_of("savefile").unwrap();
+    let checkpoint = load_checkpoint(filename);
+    let entrants: Vec<Creature> = match args.values_of("creature_ids") {
+        Some(ids) => ids.map(|id| {
+            find_creature(&checkpoint, parse_creature_id(id), filename)
+        }).collect(),
+        None => tournament::top_creatures(
+            &checkpoint.creatures,
+            args.value_of("by").unwrap().parse().unwrap(),
+            args.value_of("top").unwrap().parse().unwrap(),
+        ),
+    };
+    let mut rng = match app.value_of("seed") {
+        Some(seed) => RngState::from_seed(seed.parse().unwrap()),
+        None => checkpoint.rng.clone(),
+    };
+    let bouts = args.value_of("bouts").unwrap().parse().unwrap();
+    match Tournament::run(
+        entrants,
+        bouts,
+        &checkpoint.settings,
+        &checkpoint.rules,
+        &mut rng,
+    ) {
+        Ok(tournament) => print!("{}", tournament),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1)
+        }
+    }
+}
+
+pub fn hall_of_fame(filename: &str, args: &clap::ArgMatches) {
+    let hall_of_fame = load_checkpoint(filename).hall_of_fame;
+    if let Some(export_file) = args.value_of("export") {
+        let champions = hall_of_fame.all_champions();
+        let result = File::create(export_file).and_then(|file| {
+            match args.value_of("format") {
+                Some("dna") => export_dna(file, &champions),
+                _ => serde_json::to_writer_pretty(file, &champions)
+                    .map_err(io::Error::from),
+            }
+        });
+        match result {
+            Ok(()) => println!(
+                "Exported {} champions to {}",
+                champions.len(),
+                export_file
+            ),
+            Err(err) => {
+                eprintln!("Couldn't export to {}: {}", export_file, err);
+                process::exit(1)
+            }
+        }
+        return;
+    }
+    let rankings = match args.value_of("by") {
+        Some(ranking) => vec![ranking.parse().unwrap()],
+        None => Ranking::ALL.to_vec(),
+    };
+    for ranking in rankings {
+        println!("Most {}:", ranking);
+        for champion in hall_of_fame.champions(ranking) {
+            println!("  {}", champion);
+        }
+    }
+}
+
+/// One genome per line, each preceded by a comment saying whose it was
+fn export_dna(mut file: File, champions: &[&Champion]) -> io::Result<()> {
+    for champion in champions {
+        writeln!(file, "# {}", champion)?;
+        writeln!(file, "{}", format_bases(&champion.dna))?;
+    }
+    Ok(())
+}
+
+/// Space separated, the way they're given to `cycle-check`
+fn format_bases(dna: &dna::DNA) -> String {
+    let bases: Vec<String> = dna.bases().map(|base| base.to_string()).collect();
+    bases.join(" ")
+}
+
+fn parse_bases(bases: clap::Values) -> dna::DNA {
+    dna::DNA::from(
         bases
-            .map(|x| x.parse().expect("Well that wasn't an integer"))
+            .map(|base| {
+                base.parse().unwrap_or_else(|_| {
+                    eprintln!("'{}' isn't a valid base", base);
+                    process::exit(1)
+                })
+            })
             .collect::<Vec<i8>>(),
-    );
+    )
+}
+
+pub fn diversity(filename: &str, num_species: &str) {
+    let checkpoint = load_checkpoint(filename);
+    let mut report = diversity::analyze(&checkpoint.creatures);
+    report.species.truncate(num_species.parse().unwrap());
+    print!("{}", report);
+}
+
+pub fn export_tree(filename: &str, args: &clap::ArgMatches) {
+    let ids: Vec<CreatureID> = match args.values_of("creature_ids") {
+        Some(ids) => ids.map(parse_creature_id).collect(),
+        None => load_checkpoint(filename)
+            .creatures
+            .iter()
+            .map(|creature| creature.id)
+            .collect(),
+    };
+    let log = LineageLog::for_savefile(filename);
+    let lineage = match log.read_all() {
+        Ok(births) => Lineage::new(births),
+        Err(err) => {
+            eprintln!("Couldn't read lineage from {}: {}", log.filename(), err);
+            process::exit(1)
+        }
+    };
+    let tree = match args.value_of("format") {
+        Some("newick") => lineage.to_newick(&ids),
+        _ => lineage.to_dot(&ids),
+    };
+    match args.value_of("output") {
+        Some(output) => {
+            let result = File::create(output)
+                .and_then(|mut file| file.write_all(tree.as_bytes()));
+            if let Err(err) = result {
+                eprintln!("Couldn't export to {}: {}", output, err);
+                process::exit(1)
+            }
+        }
+        None => print!("{}", tree),
+    }
+}
+
+pub fn assemble(source: Option<&str>) {
+    let mut assembly = String::new();
+    let result = match source {
+        Some(filename) => File::open(filename)
+            .and_then(|mut file| file.read_to_string(&mut assembly)),
+        None => io::stdin().read_to_string(&mut assembly),
+    };
+    if let Err(err) = result {
+        eprintln!("Couldn't read {}: {}", source.unwrap_or("stdin"), err);
+        process::exit(1)
+    }
+    match assembly::assemble(&assembly) {
+        Ok(dna) => println!("{}", format_bases(&dna)),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1)
+        }
+    }
+}
+
+pub fn disassemble(bases: clap::Values) {
+    print!("{}", Disassembly::new(&parse_bases(bases)));
+}
+
+pub fn cycle_check(bases: clap::Values, trace: bool) {
+    let dna_args = parse_bases(bases);
     match simplify::cycle_detect(&dna_args) {
         Ok(_thought_cycle) => println!("Got a cycle!"),
-        Err(failure) => println!("Failed to get a cycle: {:?}", failure),
+        Err(failure) => println!("Failed to get a cycle: {}", failure),
+    }
+    if trace {
+        print!("{}", Disassembly::new(&dna_args));
     }
 }
@@ -1,6 +1,10 @@
 use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
 use std::cmp::{max, min};
-use std::rc::Rc;
+use std::sync::Arc;
+
+use serde::{Serialize, Serializer};
 
 use dna;
 use dna::lex;
@@ -10,9 +14,12 @@ use parsing::Decision;
 use arena;
 use stats::{CreatureStats, GlobalStatistics};
 use rng::RngState;
+use rules::Rules;
+use seed_dna::SeedDNA;
 use simplify::{cycle_detect, ThoughtCycle};
 
-#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize)]
 pub struct CreatureID(u64);
 
 impl CreatureID {
@@ -24,6 +31,10 @@ impl CreatureID {
         self.0 == 0
     }
 
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+
     pub(crate) fn parents_to_u32(
         (CreatureID(p1), CreatureID(p2)): (CreatureID, CreatureID),
     ) -> u32 {
@@ -33,6 +44,26 @@ impl CreatureID {
     }
 }
 
+impl fmt::Display for CreatureID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u64> for CreatureID {
+    fn from(id: u64) -> CreatureID {
+        CreatureID(id)
+    }
+}
+
+impl FromStr for CreatureID {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<CreatureID, ParseIntError> {
+        s.parse().map(CreatureID)
+    }
+}
+
 #[derive(Copy, Clone, Serialize, Deserialize, Debug)]
 pub struct IDGiver {
     next_id_to_give_out: u64,
@@ -75,6 +106,18 @@ impl IDGiver {
             .collect()
     }
 
+    /// Combine the id givers from several threads back into a single
+    /// unthreaded id giver that won't hand out any id already given
+    /// out by one of them
+    pub fn merge(id_givers: &[IDGiver]) -> IDGiver {
+        let next_id_to_give_out = id_givers
+            .iter()
+            .map(|idg| idg.next_id_to_give_out)
+            .max()
+            .unwrap_or(1);
+        IDGiver::new(next_id_to_give_out, 1)
+    }
+
     pub fn next_creature_id(&mut self) -> CreatureID {
         let id = self.next_id_to_give_out;
         self.next_id_to_give_out += self.modulus;
@@ -93,7 +136,6 @@ pub struct Creature {
     dna: dna::DNA,
     inv: Vec<dna::lex::Item>,
     energy: usize,
-    #[serde(skip)]
     thought_cycle: ThoughtCycle,
 }
 
@@ -108,22 +150,18 @@ impl fmt::Display for Creature {
 }
 
 impl Creature {
-    const MAX_ENERGY: usize = 40;
-    const MAX_INV_SIZE: usize = 3;
-    const MATING_COST: usize = 3;
-    const WINNER_LIFE_BONUS: usize = 5;
-
     fn new(
         id: CreatureID,
         dna: dna::DNA,
         generation: usize,
         parents: (CreatureID, CreatureID),
+        rules: &Rules,
     ) -> Result<Creature, parsing::Failure> {
         let thought_cycle = cycle_detect(&dna)?;
         Ok(Creature {
             dna: dna,
-            inv: Vec::with_capacity(Creature::MAX_INV_SIZE),
-            energy: Creature::MAX_ENERGY,
+            inv: Vec::with_capacity(rules.max_inv_size),
+            energy: rules.max_energy,
             thought_cycle,
             generation: generation,
             signal: None,
@@ -134,22 +172,36 @@ impl Creature {
         })
     }
 
-    pub fn seed_creature(id: CreatureID) -> Creature {
-        let dna = dna::DNA::seed();
-        // We know the seed dna is valid, so unwrapping
-        let thought_cycle = cycle_detect(&dna).unwrap();
-        Creature {
-            inv: Vec::with_capacity(Creature::MAX_INV_SIZE),
-            energy: Creature::MAX_ENERGY,
-            thought_cycle,
-            dna: dna,
-            generation: 0,
-            signal: None,
-            last_action: eval::PerformableAction::NoAction,
-            id,
-            parents: (CreatureID(0), CreatureID(0)),
-            stats: CreatureStats::default(),
-        }
+    /// A creature with the usual seed DNA. Populations are seeded from
+    /// a `SeedDNA`, so this is for tests that need a creature or two.
+    #[cfg(test)]
+    pub fn seed_creature(id: CreatureID, rules: &Rules) -> Creature {
+        Creature::seeded(id, dna::DNA::seed(), rules).unwrap()
+    }
+
+    /// A creature with no parents, to start a population with
+    pub fn seeded(
+        id: CreatureID,
+        dna: dna::DNA,
+        rules: &Rules,
+    ) -> Result<Creature, parsing::Failure> {
+        Creature::new(id, dna, 0, (CreatureID(0), CreatureID(0)), rules)
+    }
+
+    pub fn dna(&self) -> &dna::DNA {
+        &self.dna
+    }
+
+    /// The simplified decisions the creature cycles through, which is
+    /// all that decides how it behaves
+    pub fn thoughts(&self) -> &[Arc<Decision>] {
+        self.thought_cycle.thoughts()
+    }
+
+    /// Everything there is to know about a creature, including its
+    /// decompiled strategy, in a form meant for humans
+    pub fn details(&self) -> CreatureDetails<'_> {
+        CreatureDetails(self)
     }
 
     pub fn hash(&self) -> u32 {
@@ -157,7 +209,7 @@ impl Creature {
             .seeded_hash(CreatureID::parents_to_u32(self.parents))
     }
 
-    pub fn next_decision(&mut self) -> Rc<Decision> {
+    pub fn next_decision(&mut self) -> Arc<Decision> {
         self.thought_cycle.next()
     }
 
@@ -205,8 +257,8 @@ impl Creature {
         !self.inv.is_empty()
     }
 
-    pub fn add_item(&mut self, item: dna::lex::Item) {
-        if self.inv.len() < Creature::MAX_INV_SIZE {
+    pub fn add_item(&mut self, item: dna::lex::Item, rules: &Rules) {
+        if self.inv.len() < rules.max_inv_size {
             self.inv.push(item)
         } else {
             debug!("{} tries to add {:?} but has no more space", self, item);
@@ -235,10 +287,10 @@ impl Creature {
         }
     }
 
-    fn eat(&mut self, item: dna::lex::Item) {
+    fn eat(&mut self, item: dna::lex::Item, rules: &Rules) {
         let energy_gain = 3 * item as usize;
         debug!("{} gains {} life from {:?}", self, energy_gain, item);
-        self.gain_energy(energy_gain)
+        self.gain_energy(energy_gain, rules)
     }
 
     pub fn dead(&self) -> bool {
@@ -249,9 +301,9 @@ impl Creature {
         self.energy > 0 && (!self.is_feeder() || self.has_items())
     }
 
-    pub fn steal_from(&mut self, other: &mut Creature) {
+    pub fn steal_from(&mut self, other: &mut Creature, rules: &Rules) {
         if let Some(item) = other.pop_item() {
-            self.add_item(item)
+            self.add_item(item, rules)
         }
     }
 
@@ -263,13 +315,13 @@ impl Creature {
         self.energy = self.energy.saturating_sub(amount)
     }
 
-    pub fn gain_energy(&mut self, amount: usize) {
+    pub fn gain_energy(&mut self, amount: usize, rules: &Rules) {
         self.energy += amount;
-        self.energy = min(Creature::MAX_ENERGY, self.energy);
+        self.energy = min(rules.max_energy, self.energy);
     }
 
-    pub fn gain_winner_energy(&mut self, rng: &mut RngState) {
-        self.gain_energy(rng.rand_range(0, Creature::WINNER_LIFE_BONUS))
+    pub fn gain_winner_energy(&mut self, rng: &mut RngState, rules: &Rules) {
+        self.gain_energy(rng.rand_range(0, rules.winner_life_bonus), rules)
     }
 
     pub fn kill(&mut self) {
@@ -290,6 +342,7 @@ impl Creature {
         id_giver: &mut IDGiver,
         rng: &mut RngState,
         mutation_rate: f64,
+        rules: &Rules,
     ) -> (Result<Creature, parsing::Failure>, GlobalStatistics) {
         let (child_dna, stats) =
             dna::DNA::combine(&self.dna, &other.dna, rng, mutation_rate);
@@ -298,6 +351,7 @@ impl Creature {
             child_dna,                                  // dna
             max(self.generation, other.generation) + 1, // generation
             (self.id, other.id),                        // parents
+            rules,
         );
         if maybe_child.is_ok() {
             self.stats.num_children += 1;
@@ -306,8 +360,8 @@ impl Creature {
         (maybe_child, stats)
     }
 
-    pub fn pay_for_mating(&mut self, share: usize) -> bool {
-        let mut cost = (Creature::MATING_COST as f64 * (share as f64 / 100.0))
+    pub fn pay_for_mating(&mut self, share: usize, rules: &Rules) -> bool {
+        let mut cost = (rules.mating_cost as f64 * (share as f64 / 100.0))
             .round() as isize;
         while cost > 0 {
             match self.pop_item() {
@@ -323,10 +377,25 @@ impl Creature {
         true
     }
 
+    /// The rolls for this creature trying to flee from `other`. They
+    /// only depend on the two creatures, so this can be called ahead
+    /// of time to see how an attempt will go.
+    pub fn flee_attempt(&self, other: &Creature) -> FleeAttempt {
+        let mut rng = RngState::from_creatures(self, other);
+        let my_roll = rng.rand_range(0, self.energy);
+        let other_roll = rng.rand_range(0, other.energy);
+        FleeAttempt {
+            my_roll,
+            other_roll,
+            damage: rng.rand_range(0, 4),
+        }
+    }
+
     pub fn carryout(
         &mut self,
         other: &mut Creature,
         action: eval::PerformableAction,
+        rules: &Rules,
     ) -> arena::FightStatus {
         if self.is_feeder() {
             debug!("Feeder does nothing");
@@ -340,7 +409,7 @@ impl Creature {
             eval::PerformableAction::Eat => match self.pop_item() {
                 Some(item) => {
                     info!("{} eats {:?}", self, self.top_item());
-                    self.eat(item);
+                    self.eat(item, rules);
                 }
                 None => debug!(
                     "{} tries to eat an item, but \
@@ -351,7 +420,7 @@ impl Creature {
             eval::PerformableAction::Take => match other.pop_item() {
                 Some(item) => {
                     info!("{} takes {:?} from {}", self, item, other);
-                    self.add_item(item);
+                    self.add_item(item, rules);
                 }
                 None => {
                     debug!(
@@ -367,17 +436,14 @@ impl Creature {
                 debug!("{} defends with {:?} fruitlessly", self, dmg)
             }
             eval::PerformableAction::Flee => {
-                let mut rng = RngState::from_creatures(self, other);
-                let my_roll = rng.rand_range(0, self.energy);
-                let other_roll = rng.rand_range(0, other.energy);
-                let dmg = rng.rand_range(0, 4);
-                if other_roll < my_roll {
+                let attempt = self.flee_attempt(other);
+                if attempt.escaped() {
                     info!(
                         "{} flees the encounter and takes \
                          {} damage",
-                        self, dmg
+                        self, attempt.damage
                     );
-                    self.lose_energy(dmg);
+                    self.lose_energy(attempt.damage);
                     return arena::FightStatus::End;
                 } else {
                     debug!("{} tries to flee, but {} prevents it", self, other);
@@ -391,19 +457,76 @@ impl Creature {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct FleeAttempt {
+    pub my_roll: usize,
+    pub other_roll: usize,
+    /// Damage taken on the way out, if the flight succeeds
+    pub damage: usize,
+}
+
+impl FleeAttempt {
+    pub fn escaped(&self) -> bool {
+        self.other_roll < self.my_roll
+    }
+}
+
+pub struct CreatureDetails<'a>(&'a Creature);
+
+impl<'a> fmt::Display for CreatureDetails<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let creature = self.0;
+        let (CreatureID(parent1), CreatureID(parent2)) = creature.parents;
+        writeln!(f, "{}", creature)?;
+        writeln!(f, "Generation: {}", creature.generation)?;
+        if creature.generation == 0 {
+            writeln!(f, "Parents: none, it's a seed creature")?;
+        } else {
+            writeln!(f, "Parents: {} and {}", parent1, parent2)?;
+        }
+        writeln!(f, "Energy: {}", creature.energy)?;
+        if creature.inv.is_empty() {
+            writeln!(f, "Inventory: empty")?;
+        } else {
+            writeln!(f, "Inventory: {:?}", creature.inv)?;
+        }
+        writeln!(f, "Kills: {}", creature.stats.kills)?;
+        writeln!(f, "Children: {}", creature.stats.num_children)?;
+        writeln!(f, "Encounters survived: {}", creature.stats.survived)?;
+        writeln!(f, "Feeders eaten: {}", creature.stats.eaten)?;
+        let thoughts = creature.thought_cycle.thoughts();
+        writeln!(
+            f,
+            "Thought cycle of {} thoughts, next is thought {}:",
+            thoughts.len(),
+            creature.thought_cycle.cycle_offset() + 1
+        )?;
+        for (i, thought) in thoughts.iter().enumerate() {
+            writeln!(f, "Thought {}:", i + 1)?;
+            write!(f, "{}", thought.tree)?;
+        }
+        Ok(())
+    }
+}
+
 /// Needed because some parts aren't serialized because they can be
-/// inferred from other fields
-#[derive(Deserialize)]
+/// inferred from other fields. The fields are in the same order as
+/// `Creature`'s, since binary checkpoints don't have field names.
+/// Serializing one gives back what it was deserialized from, which is
+/// how older binary checkpoints are turned into JSON to migrate them.
+#[derive(Deserialize, Serialize)]
 pub struct DeserializableCreature {
-    dna: dna::DNA,
-    inv: Vec<dna::lex::Item>,
-    energy: usize,
+    id: CreatureID,
     generation: usize,
     signal: Option<dna::lex::Signal>,
     last_action: eval::PerformableAction,
-    id: CreatureID,
     parents: (CreatureID, CreatureID),
     stats: CreatureStats,
+    dna: dna::DNA,
+    inv: Vec<dna::lex::Item>,
+    energy: usize,
+    #[serde(default)]
+    thought_cycle: Option<usize>,
 }
 
 impl DeserializableCreature {
@@ -418,9 +541,13 @@ impl DeserializableCreature {
             id,
             parents,
             stats,
+            thought_cycle: cycle_offset,
         } = self;
         // Invalid creatures are never serialized, so unwrapping
-        let thought_cycle = cycle_detect(&dna).unwrap();
+        let mut thought_cycle = cycle_detect(&dna).unwrap();
+        if let Some(cycle_offset) = cycle_offset {
+            thought_cycle.resume_at(cycle_offset);
+        }
         Creature {
             dna,
             inv,
@@ -436,27 +563,66 @@ impl DeserializableCreature {
     }
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 pub struct Creatures {
     creatures: Vec<Creature>,
     max_pop_size: usize,
     feeder_count: usize,
-    #[serde(skip)]
     rng: RngState,
-    #[serde(skip)]
+    #[serde(serialize_with = "serialize_some")]
     id_giver: IDGiver,
 }
 
+/// The parts of a population split by thread that are lost when it's
+/// merged back together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Island {
+    /// How many of the merged creatures are on this island. They come
+    /// straight after the previous island's.
+    size: usize,
+    max_pop_size: usize,
+    feeder_count: usize,
+    rng: RngState,
+    id_giver: IDGiver,
+}
+
+/// Serializes a value the way `Some(value)` would be, for fields that
+/// are optional when deserializing. JSON can't tell the difference,
+/// but binary checkpoints can.
+fn serialize_some<T: Serialize, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_some(value)
+}
+
 impl Creatures {
     fn from_pieces(
         id_giver: IDGiver,
         max_pop_size: usize,
-        rng: RngState,
+        mut rng: RngState,
+        rules: &Rules,
+        seed_dna: &SeedDNA,
     ) -> Creatures {
-        let mut idgv = id_giver;
-        let creatures = (0..max_pop_size)
-            .map(|_idx| Creature::seed_creature(idgv.next_creature_id()))
-            .collect();
+        let mut id_giver = id_giver;
+        let mut creatures = Vec::with_capacity(max_pop_size);
+        for (dna, count) in seed_dna.counts(max_pop_size) {
+            for _ in 0..count {
+                // Seed DNA is checked for a thought cycle when it's read
+                creatures.push(
+                    Creature::seeded(
+                        id_giver.next_creature_id(),
+                        dna.clone(),
+                        rules,
+                    ).unwrap(),
+                );
+            }
+        }
+        // A population of one genome is the same however it's ordered,
+        // and leaving it alone keeps the rng where it always was
+        if seed_dna.len() > 1 {
+            rng.shuffle(&mut creatures);
+        }
         Creatures {
             creatures,
             max_pop_size,
@@ -466,11 +632,30 @@ impl Creatures {
         }
     }
 
-    pub fn new(max_pop_size: usize) -> Creatures {
+    /// A population of nothing but the usual seed DNA, for tests
+    #[cfg(test)]
+    pub fn new(
+        max_pop_size: usize,
+        rng: RngState,
+        rules: &Rules,
+    ) -> Creatures {
+        Creatures::seeded(max_pop_size, rng, rules, &SeedDNA::default())
+    }
+
+    /// Create a new population with genomes from `seed_dna` instead of
+    /// the usual seed DNA
+    pub fn seeded(
+        max_pop_size: usize,
+        rng: RngState,
+        rules: &Rules,
+        seed_dna: &SeedDNA,
+    ) -> Creatures {
         Creatures::from_pieces(
             IDGiver::unthreaded(),
             max_pop_size,
-            RngState::default(),
+            rng,
+            rules,
+            seed_dna,
         )
     }
 
@@ -478,18 +663,24 @@ impl Creatures {
     pub fn per_thread(
         num_threads: usize,
         max_pop_size: usize,
+        rng: &mut RngState,
+        rules: &Rules,
+        seed_dna: &SeedDNA,
     ) -> Vec<Creatures> {
-        assert!(
-            max_pop_size % num_threads == 0,
-            "Max population size must be a multiple of the number of threads"
-        );
-        let pop_per_thread = max_pop_size / num_threads;
-        let mut rng = RngState::default();
+        let pop_rem = max_pop_size % num_threads;
+        let pop_div = max_pop_size / num_threads;
 
         IDGiver::per_thread(num_threads)
             .into_iter()
-            .map(|id_giver| {
-                Creatures::from_pieces(id_giver, pop_per_thread, rng.spawn())
+            .enumerate()
+            .map(|(i, id_giver)| {
+                Creatures::from_pieces(
+                    id_giver,
+                    if i >= pop_rem { pop_div } else { pop_div + 1 },
+                    rng.spawn(),
+                    rules,
+                    seed_dna,
+                )
             })
             .collect()
     }
@@ -532,6 +723,61 @@ impl Creatures {
             .collect()
     }
 
+    /// Split a population back up into the islands it was merged
+    /// from, exactly as they were when `islands` was called on them
+    pub fn split_into_islands(self, islands: &[Island]) -> Vec<Creatures> {
+        let mut creatures = self.creatures.into_iter();
+        islands
+            .iter()
+            .map(|island| Creatures {
+                creatures: creatures.by_ref().take(island.size).collect(),
+                max_pop_size: island.max_pop_size,
+                feeder_count: island.feeder_count,
+                rng: island.rng.clone(),
+                id_giver: island.id_giver,
+            })
+            .collect()
+    }
+
+    /// What `merge` loses about each of `populations`, so they can be
+    /// split back up exactly instead of evenly
+    pub fn islands(populations: &[Creatures]) -> Vec<Island> {
+        populations
+            .iter()
+            .map(|population| Island {
+                size: population.len(),
+                max_pop_size: population.max_pop_size,
+                feeder_count: population.feeder_count,
+                rng: population.rng.clone(),
+                id_giver: population.id_giver,
+            })
+            .collect()
+    }
+
+    /// Merge populations that were split by thread back into a single
+    /// population. This is the inverse of `split_by_thread`. The
+    /// merged population carries on with the first one's rng.
+    pub fn merge(populations: Vec<Creatures>) -> Creatures {
+        let id_givers: Vec<IDGiver> =
+            populations.iter().map(|pop| pop.id_giver).collect();
+        let mut merged = Creatures {
+            creatures: Vec::new(),
+            max_pop_size: 0,
+            feeder_count: 0,
+            rng: populations
+                .first()
+                .map(|pop| pop.rng.clone())
+                .unwrap_or_default(),
+            id_giver: IDGiver::merge(&id_givers),
+        };
+        for population in populations {
+            merged.creatures.extend(population.creatures);
+            merged.max_pop_size += population.max_pop_size;
+            merged.feeder_count += population.feeder_count;
+        }
+        merged
+    }
+
     pub fn id_giver(&mut self) -> &mut IDGiver {
         &mut self.id_giver
     }
@@ -540,6 +786,31 @@ impl Creatures {
         self.creatures.len()
     }
 
+    /// Change the maximum population size. If the population is
+    /// already bigger, nobody is killed, but no more feeders will
+    /// appear until it shrinks back down.
+    pub fn set_max_pop_size(&mut self, max_pop_size: usize) {
+        self.max_pop_size = max_pop_size;
+        self.feeder_count = min(
+            self.feeder_count,
+            max_pop_size.saturating_sub(self.len()),
+        );
+    }
+
+    pub fn iter(&self) -> ::std::slice::Iter<'_, Creature> {
+        self.creatures.iter()
+    }
+
+    pub fn get(&self, id: CreatureID) -> Option<&Creature> {
+        self.creatures.iter().find(|creature| creature.id == id)
+    }
+
+    /// Remove a specific creature from the population
+    pub fn take(&mut self, id: CreatureID) -> Option<Creature> {
+        let index = self.creatures.iter().position(|c| c.id == id)?;
+        Some(self.creatures.swap_remove(index))
+    }
+
     pub fn refill_feeders(&mut self) {
         if self.len() + self.feeder_count < self.max_pop_size {
             self.feeder_count =
@@ -556,6 +827,11 @@ impl Creatures {
         self.creatures.swap_remove(index)
     }
 
+    pub fn take_feeder(&mut self) -> Creature {
+        self.feeder_count -= 1;
+        Creature::feeder()
+    }
+
     pub fn random_creature_or_feeder(&mut self) -> Creature {
         let index = self.rng
             .rand_range(0, self.creatures.len() + self.feeder_count);
@@ -588,11 +864,15 @@ impl Creatures {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct DeserializableCreatures {
     creatures: Vec<DeserializableCreature>,
     max_pop_size: usize,
     feeder_count: usize,
+    #[serde(default)]
+    rng: RngState,
+    #[serde(default)]
+    id_giver: Option<IDGiver>,
 }
 
 impl DeserializableCreatures {
@@ -601,6 +881,8 @@ impl DeserializableCreatures {
             creatures: deserialized_creatures,
             max_pop_size,
             feeder_count,
+            rng,
+            id_giver,
         } = self;
         let max_id = deserialized_creatures
             .iter()
@@ -613,8 +895,10 @@ impl DeserializableCreatures {
             creatures,
             max_pop_size,
             feeder_count,
-            rng: RngState::default(),
-            id_giver: IDGiver::new(max_id + 1, 1),
+            rng,
+            // Older checkpoints didn't save the id giver
+            id_giver: id_giver
+                .unwrap_or_else(|| IDGiver::new(max_id + 1, 1)),
         }
     }
 }
@@ -622,9 +906,59 @@ impl DeserializableCreatures {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use assembly;
+    use bincode;
+    use serde_json;
+
+    #[test]
+    fn details_show_a_compared_subcondition_on_its_own_lines() {
+        let dna = assembly::assemble(
+            "me_last_act subcondition less_than me energy literal 5 \
+             flee wait attack fire defend ice",
+        ).unwrap();
+        let creature =
+            Creature::seeded(CreatureID(1), dna, &Rules::default()).unwrap();
+        let thought = "Thought 1:\n\
+                       if my last action was what this decides: {\n    \
+                       if my energy is less than 5 {\n        \
+                       flee the encounter\n    \
+                       } else {\n        \
+                       wait\n    \
+                       }\n\
+                       } then {\n    \
+                       attack with fire\n\
+                       } else {\n    \
+                       defend against ice\n\
+                       }\n";
+        assert!(creature.details().to_string().contains(thought));
+    }
+
+    #[test]
+    fn deserializable_creatures_have_the_same_field_order() {
+        // Binary checkpoints only have the order of the fields to go
+        // on, so a mix-up shows as fields with each other's values
+        let rules = Rules::default();
+        let mut creatures = Creatures::new(5, RngState::from_seed(2), &rules);
+        {
+            let creature = &mut creatures.creatures[0];
+            creature.generation = 7;
+            creature.energy = 11;
+            creature.parents = (CreatureID(3), CreatureID(4));
+            creature.stats.kills = 5;
+            creature.stats.num_children = 6;
+        }
+        let bytes = bincode::serialize(&creatures).unwrap();
+        let deserialized: DeserializableCreatures =
+            bincode::deserialize(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_value(&deserialized).unwrap(),
+            serde_json::to_value(&creatures).unwrap()
+        );
+    }
 
     #[test]
     fn split_by_thread_divides_evenly() {
+        let rules = Rules::default();
         let id_giver = IDGiver::new(14, 1);
         let creats = Creatures {
             id_giver,
@@ -632,13 +966,13 @@ mod tests {
             feeder_count: 3,
             max_pop_size: 10,
             creatures: vec![
-                Creature::seed_creature(CreatureID(1)),
-                Creature::seed_creature(CreatureID(3)),
-                Creature::seed_creature(CreatureID(5)),
-                Creature::seed_creature(CreatureID(7)),
-                Creature::seed_creature(CreatureID(9)),
-                Creature::seed_creature(CreatureID(11)),
-                Creature::seed_creature(CreatureID(13)),
+                Creature::seed_creature(CreatureID(1), &rules),
+                Creature::seed_creature(CreatureID(3), &rules),
+                Creature::seed_creature(CreatureID(5), &rules),
+                Creature::seed_creature(CreatureID(7), &rules),
+                Creature::seed_creature(CreatureID(9), &rules),
+                Creature::seed_creature(CreatureID(11), &rules),
+                Creature::seed_creature(CreatureID(13), &rules),
             ],
         };
         let mut res = creats.split_by_thread(3);
@@ -671,4 +1005,57 @@ mod tests {
         assert_eq!(three.id_giver.next_id_to_give_out, 16);
         assert_eq!(three.id_giver.modulus, 3);
     }
+
+    #[test]
+    fn merge_undoes_split_by_thread() {
+        let rules = Rules::default();
+        let creats = Creatures {
+            id_giver: IDGiver::new(14, 1),
+            rng: RngState::default(),
+            feeder_count: 3,
+            max_pop_size: 10,
+            creatures: vec![
+                Creature::seed_creature(CreatureID(1), &rules),
+                Creature::seed_creature(CreatureID(3), &rules),
+                Creature::seed_creature(CreatureID(5), &rules),
+                Creature::seed_creature(CreatureID(7), &rules),
+                Creature::seed_creature(CreatureID(9), &rules),
+            ],
+        };
+        let mut split = creats.split_by_thread(3);
+        // Simulate each thread handing out some ids
+        split[0].id_giver().next_creature_id();
+        split[2].id_giver().next_creature_id();
+        split[2].id_giver().next_creature_id();
+        let merged = Creatures::merge(split);
+        assert_eq!(merged.creatures.len(), 5);
+        assert_eq!(merged.creatures[0].id, CreatureID(1));
+        assert_eq!(merged.creatures[4].id, CreatureID(9));
+        assert_eq!(merged.max_pop_size, 10);
+        assert_eq!(merged.feeder_count, 3);
+        // thread 3 gave out 16 and 19, so 22 is the next free id
+        assert_eq!(merged.id_giver.next_id_to_give_out, 22);
+        assert_eq!(merged.id_giver.modulus, 1);
+    }
+
+    #[test]
+    fn split_into_islands_undoes_merge() {
+        let rules = Rules::default();
+        let mut populations = Creatures::per_thread(
+            3,
+            10,
+            &mut RngState::default(),
+            &rules,
+            &SeedDNA::default(),
+        );
+        populations[1].creatures.truncate(1);
+        populations[2].id_giver().next_creature_id();
+        let islands = Creatures::islands(&populations);
+        let split =
+            Creatures::merge(populations.clone()).split_into_islands(&islands);
+        assert_eq!(
+            serde_json::to_value(&split).unwrap(),
+            serde_json::to_value(&populations).unwrap()
+        );
+    }
 }
@@ -0,0 +1,552 @@
+//! Ways of watching and nudging a running simulation besides the
+//! status line. The `Dashboard` is shared between the simulation and
+//! whatever is showing it: a small HTTP server on localhost for
+//! machines nobody is logged in to, which serves the same numbers as
+//! the status line as JSON and in the Prometheus text format plus a
+//! page with buttons to save and pause, or the terminal UI in `tui`.
+
+use std::collections::VecDeque;
+use std::fmt::Write as FmtWrite;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde_json;
+
+use creatures::{Creature, Creatures};
+use dna::lex::{DamageType, Signal};
+use stats::{GlobalStatistics, Ranking};
+use tournament;
+
+/// Everything the status line shows, as of the last time it was
+/// printed
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Status {
+    pub population: usize,
+    pub feeders: usize,
+    pub events_per_second: u64,
+    /// How far off the last guess at events per status update was.
+    /// Only the single threaded simulation makes guesses.
+    pub prediction_error: Option<f64>,
+    pub fps: f64,
+    pub paused: bool,
+    pub stats: GlobalStatistics,
+}
+
+/// One of the best creatures in the population, ready to be shown
+#[derive(Debug, Clone)]
+pub struct Leader {
+    pub summary: String,
+    /// The decompiled thought cycle as lines of pseudo-code
+    pub strategy: Vec<String>,
+}
+
+impl From<&Creature> for Leader {
+    fn from(creature: &Creature) -> Leader {
+        Leader {
+            summary: format!(
+                "{} gen: {} kills: {} children: {} survived: {}",
+                creature,
+                creature.generation,
+                creature.stats.kills,
+                creature.stats.num_children,
+                creature.stats.survived
+            ),
+            strategy: creature
+                .thoughts()
+                .iter()
+                .enumerate()
+                .flat_map(|(i, decision)| {
+                    let thought = format!("Thought {}:", i + 1);
+                    let tree = decision.tree.to_string();
+                    let lines: Vec<String> = tree
+                        .lines()
+                        .map(|line| format!("  {}", line))
+                        .collect();
+                    Some(thought).into_iter().chain(lines)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Shared between the simulation and whatever is showing it
+#[derive(Default)]
+pub struct Dashboard {
+    status: Mutex<Status>,
+    leaders: Mutex<Vec<Leader>>,
+    /// Things the simulation would have printed if it wasn't sharing
+    /// the terminal, oldest first
+    messages: Mutex<VecDeque<String>>,
+    paused: AtomicBool,
+    save_requested: AtomicBool,
+    quit_requested: AtomicBool,
+    /// Set while the terminal UI is drawing on the terminal
+    owns_terminal: AtomicBool,
+}
+
+impl Dashboard {
+    /// How long a paused simulation sleeps between checking whether
+    /// it's been unpaused
+    pub const PAUSE_POLL: Duration = Duration::from_millis(100);
+    /// How many of the best creatures are shown
+    const NUM_LEADERS: usize = 3;
+    /// How often the best creatures are picked out again, since it
+    /// means going through the whole population
+    pub const LEADERS_INTERVAL: Duration = Duration::from_secs(1);
+    /// How many lines of messages are kept
+    const MAX_MESSAGES: usize = 100;
+
+    pub fn new() -> Dashboard {
+        Dashboard::default()
+    }
+
+    /// Starts serving on `127.0.0.1:port` in a background thread.
+    /// Only fails if the port can't be bound.
+    pub fn serve(dashboard: Arc<Dashboard>, port: u16) -> io::Result<()> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                // A misbehaving client only hurts itself
+                let _ = dashboard.handle(stream);
+            }
+        });
+        Ok(())
+    }
+
+    pub fn update(&self, status: Status) {
+        *self.status.lock().unwrap() = Status {
+            paused: self.is_paused(),
+            ..status
+        };
+    }
+
+    pub fn status(&self) -> Status {
+        *self.status.lock().unwrap()
+    }
+
+    /// Picks out the creatures with the most kills. This goes through
+    /// the whole population, so it shouldn't be done every event.
+    pub fn update_leaders(&self, population: &Creatures) {
+        let leaders = tournament::top_creatures(
+            population,
+            Ranking::Kills,
+            Dashboard::NUM_LEADERS,
+        );
+        *self.leaders.lock().unwrap() =
+            leaders.iter().map(Leader::from).collect();
+    }
+
+    pub fn leaders(&self) -> Vec<Leader> {
+        self.leaders.lock().unwrap().clone()
+    }
+
+    /// Keeps a message to show, one line at a time
+    pub fn log(&self, message: &str) {
+        let mut messages = self.messages.lock().unwrap();
+        for line in message.lines() {
+            messages.push_back(line.to_owned());
+        }
+        while messages.len() > Dashboard::MAX_MESSAGES {
+            messages.pop_front();
+        }
+    }
+
+    /// The last `n` lines of messages, oldest first
+    pub fn messages(&self, n: usize) -> Vec<String> {
+        let messages = self.messages.lock().unwrap();
+        let skip = messages.len().saturating_sub(n);
+        messages.iter().skip(skip).cloned().collect()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn toggle_pause(&self) {
+        self.paused.fetch_xor(true, Ordering::Relaxed);
+    }
+
+    pub fn request_save(&self) {
+        self.save_requested.store(true, Ordering::Relaxed);
+    }
+
+    /// Asks the simulation to save and stop
+    pub fn request_quit(&self) {
+        self.quit_requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested.load(Ordering::Relaxed)
+    }
+
+    pub fn owns_terminal(&self) -> bool {
+        self.owns_terminal.load(Ordering::Relaxed)
+    }
+
+    pub fn set_owns_terminal(&self, owns_terminal: bool) {
+        self.owns_terminal.store(owns_terminal, Ordering::Relaxed);
+    }
+
+    /// Whether someone asked for a save since the last time this was
+    /// called
+    pub fn take_save_request(&self) -> bool {
+        self.save_requested.swap(false, Ordering::Relaxed)
+    }
+
+    /// Blocks for as long as the simulation is paused, unless it's
+    /// asked to quit
+    pub fn wait_while_paused(&self) {
+        while self.is_paused() && !self.quit_requested() {
+            thread::sleep(Dashboard::PAUSE_POLL);
+        }
+    }
+
+    fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        // Only the request line matters, but the headers have to be
+        // read before responding or some clients get upset
+        while !request.windows(4).any(|w| w == b"\r\n\r\n")
+            && request.len() < 8192
+        {
+            let read = stream.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..read]);
+        }
+        let request = String::from_utf8_lossy(&request);
+        let mut words = request.split_whitespace();
+        let method = words.next().unwrap_or("");
+        let path = words.next().unwrap_or("");
+        // Any web page can send a form here, so the buttons only work
+        // from the dashboard's own page
+        let response = if method == "POST" && !same_origin(&request) {
+            Response {
+                status: "403 Forbidden",
+                content_type: "text/plain",
+                body: "Forbidden\n".to_owned(),
+                location: None,
+            }
+        } else {
+            self.respond(method, path)
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n",
+            response.status,
+            response.content_type,
+            response.body.len()
+        )?;
+        if let Some(location) = response.location {
+            write!(stream, "Location: {}\r\n", location)?;
+        }
+        write!(stream, "Connection: close\r\n\r\n{}", response.body)?;
+        stream.flush()
+    }
+
+    fn respond(&self, method: &str, path: &str) -> Response {
+        let status = *self.status.lock().unwrap();
+        match (method, path) {
+            ("GET", "/") => Response::ok("text/html", PAGE.to_owned()),
+            ("GET", "/status") => Response::ok(
+                "application/json",
+                serde_json::to_string(&status).unwrap(),
+            ),
+            ("GET", "/metrics") => {
+                Response::ok("text/plain; version=0.0.4", prometheus(&status))
+            }
+            ("POST", "/save") => {
+                self.request_save();
+                Response::see_other("/")
+            }
+            ("POST", "/pause") => {
+                self.paused.store(true, Ordering::Relaxed);
+                Response::see_other("/")
+            }
+            ("POST", "/resume") => {
+                self.paused.store(false, Ordering::Relaxed);
+                Response::see_other("/")
+            }
+            ("GET", _) | ("POST", _) => Response {
+                status: "404 Not Found",
+                content_type: "text/plain",
+                body: "Not found\n".to_owned(),
+                location: None,
+            },
+            _ => Response {
+                status: "405 Method Not Allowed",
+                content_type: "text/plain",
+                body: "Method not allowed\n".to_owned(),
+                location: None,
+            },
+        }
+    }
+}
+
+/// The value of a header in a request, if it's there
+fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+    request
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// Whether a request came from a page served by the dashboard. The
+/// host has to be localhost, so another site can't point its own
+/// domain at 127.0.0.1, and browsers say where a form was sent from
+/// in the `Origin` header. Requests from outside a browser, like
+/// curl's, don't have one.
+fn same_origin(request: &str) -> bool {
+    let host = match header(request, "Host") {
+        Some(host) => host,
+        None => return false,
+    };
+    let name = host.rsplit_once(':').map_or(host, |(name, _)| name);
+    if name != "127.0.0.1" && name != "localhost" {
+        return false;
+    }
+    match header(request, "Origin") {
+        Some(origin) => origin == format!("http://{}", host),
+        None => true,
+    }
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+    location: Option<&'static str>,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Response {
+        Response {
+            status: "200 OK",
+            content_type,
+            body,
+            location: None,
+        }
+    }
+
+    fn see_other(location: &'static str) -> Response {
+        Response {
+            status: "303 See Other",
+            content_type: "text/plain",
+            body: String::new(),
+            location: Some(location),
+        }
+    }
+}
+
+/// Whether the status line should be left out because something else
+/// is drawing on the terminal
+pub fn terminal_taken(dashboard: &Option<Arc<Dashboard>>) -> bool {
+    dashboard.as_ref().is_some_and(|d| d.owns_terminal())
+}
+
+/// Prints a message, or keeps it for the terminal UI to show if it's
+/// drawing on the terminal
+pub fn announce(dashboard: &Option<Arc<Dashboard>>, message: &str) {
+    match *dashboard {
+        Some(ref dashboard) if dashboard.owns_terminal() => {
+            dashboard.log(message.trim_matches('\n'))
+        }
+        _ => println!("{}", message),
+    }
+}
+
+/// The status in the Prometheus text exposition format
+fn prometheus(status: &Status) -> String {
+    let mut out = String::new();
+    {
+        let mut metric = |name: &str, kind: &str, help: &str, value: f64| {
+            writeln!(out, "# HELP evofighters_{} {}", name, help).unwrap();
+            writeln!(out, "# TYPE evofighters_{} {}", name, kind).unwrap();
+            writeln!(out, "evofighters_{} {}", name, value).unwrap();
+        };
+        let stats = &status.stats;
+        metric(
+            "population",
+            "gauge",
+            "Creatures alive",
+            status.population as f64,
+        );
+        metric("feeders", "gauge", "Feeders waiting", status.feeders as f64);
+        metric(
+            "events_per_second",
+            "gauge",
+            "Events run per second",
+            status.events_per_second as f64,
+        );
+        metric(
+            "paused",
+            "gauge",
+            "Whether the simulation is paused",
+            if status.paused { 1.0 } else { 0.0 },
+        );
+        metric("events_total", "counter", "Events run", stats.events as f64);
+        metric(
+            "mutations_total",
+            "counter",
+            "Mutations",
+            stats.mutations as f64,
+        );
+        metric(
+            "births_total",
+            "counter",
+            "Children born",
+            stats.children_born as f64,
+        );
+        metric("kills_total", "counter", "Kills", stats.kills as f64);
+        metric(
+            "feeders_eaten_total",
+            "counter",
+            "Feeders eaten",
+            stats.feeders_eaten as f64,
+        );
+        metric("rounds_total", "counter", "Rounds fought", stats.rounds as f64);
+    }
+    let actions = &status.stats.actions;
+    writeln!(out, "# HELP evofighters_actions_total Actions chosen").unwrap();
+    writeln!(out, "# TYPE evofighters_actions_total counter").unwrap();
+    let damage_types =
+        [DamageType::Fire, DamageType::Ice, DamageType::Electricity];
+    for &dmg in &damage_types {
+        writeln!(
+            out,
+            "evofighters_actions_total{{action=\"attack\",type=\"{}\"}} {}",
+            dmg,
+            actions.attacks[dmg as usize]
+        ).unwrap();
+        writeln!(
+            out,
+            "evofighters_actions_total{{action=\"defend\",type=\"{}\"}} {}",
+            dmg,
+            actions.defends[dmg as usize]
+        ).unwrap();
+    }
+    let signals = [
+        Signal::Red,
+        Signal::Yellow,
+        Signal::Blue,
+        Signal::Purple,
+        Signal::Orange,
+        Signal::Green,
+    ];
+    for (&color, &count) in signals.iter().zip(&actions.signals) {
+        writeln!(
+            out,
+            "evofighters_actions_total{{action=\"signal\",color=\"{}\"}} {}",
+            color, count
+        ).unwrap();
+    }
+    for &(action, count) in &[
+        ("eat", actions.eat),
+        ("take", actions.take),
+        ("wait", actions.wait),
+        ("flee", actions.flee),
+        ("mate", actions.mate),
+    ] {
+        writeln!(
+            out,
+            "evofighters_actions_total{{action=\"{}\"}} {}",
+            action, count
+        ).unwrap();
+    }
+    out
+}
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<title>Evofighters</title>
+<style>
+body { font-family: monospace; margin: 2em; }
+form { display: inline; }
+</style>
+</head>
+<body>
+<h1>Evofighters</h1>
+<form method="post" action="/save"><button>Save</button></form>
+<form method="post" action="/pause"><button>Pause</button></form>
+<form method="post" action="/resume"><button>Resume</button></form>
+<p><a href="/status">JSON</a> <a href="/metrics">Prometheus</a></p>
+<pre id="status">Loading...</pre>
+<script>
+function refresh() {
+  fetch("/status")
+    .then(function(r) { return r.json(); })
+    .then(function(s) {
+      document.getElementById("status").textContent =
+        JSON.stringify(s, null, 2);
+    });
+}
+refresh();
+setInterval(refresh, 1000);
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buttons_change_the_controls() {
+        let dashboard = Dashboard::new();
+        assert_eq!(dashboard.respond("POST", "/pause").status, "303 See Other");
+        assert!(dashboard.is_paused());
+        dashboard.respond("POST", "/resume");
+        assert!(!dashboard.is_paused());
+        dashboard.respond("POST", "/save");
+        assert!(dashboard.take_save_request());
+        assert!(!dashboard.take_save_request());
+        assert_eq!(dashboard.respond("GET", "/nope").status, "404 Not Found");
+    }
+
+    #[test]
+    fn buttons_only_work_from_the_dashboard() {
+        let post = |headers: &str| {
+            format!("POST /pause HTTP/1.1\r\n{}\r\n", headers)
+        };
+        assert!(same_origin(&post("Host: 127.0.0.1:8080\r\n")));
+        assert!(same_origin(&post(
+            "host: localhost:8080\r\norigin: http://localhost:8080\r\n"
+        )));
+        assert!(!same_origin(&post(
+            "Host: 127.0.0.1:8080\r\nOrigin: https://example.com\r\n"
+        )));
+        assert!(!same_origin(&post("Host: evil.example.com:8080\r\n")));
+        assert!(!same_origin(&post("")));
+    }
+
+    #[test]
+    fn metrics_are_in_prometheus_format() {
+        let dashboard = Dashboard::new();
+        dashboard.update(Status {
+            population: 42,
+            stats: GlobalStatistics {
+                kills: 7,
+                ..GlobalStatistics::default()
+            },
+            ..Status::default()
+        });
+        let body = dashboard.respond("GET", "/metrics").body;
+        assert!(body.contains("\nevofighters_population 42\n"));
+        assert!(body.contains("\nevofighters_kills_total 7\n"));
+        assert!(body.contains(
+            "evofighters_actions_total{action=\"attack\",type=\"fire\"} 0"
+        ));
+    }
+}
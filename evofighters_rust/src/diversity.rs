@@ -0,0 +1,279 @@
+//! Measures how genetically and behaviourally varied a population
+//! is, to tell a monoculture apart from a thriving ecosystem.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+
+use creatures::{Creature, CreatureID, Creatures};
+use dna::ast;
+use eval::PerformableAction;
+use simplify;
+use simplify::Situation;
+
+/// A group of creatures whose thought cycles always decide on the same
+/// actions, so they behave identically in a fight even if their DNA
+/// and the way their decisions are written differ
+#[derive(Debug, Clone)]
+pub struct Species {
+    pub size: usize,
+    pub oldest_generation: usize,
+    pub newest_generation: usize,
+    /// The creature in the species with the lowest id, to look at with
+    /// `show`
+    pub example: CreatureID,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LengthDistribution {
+    pub min: usize,
+    pub median: usize,
+    pub mean: f64,
+    pub max: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiversityReport {
+    pub creatures: usize,
+    pub distinct_genomes: usize,
+    /// Shannon diversity of the genomes, in nats. Zero means every
+    /// creature has the same DNA.
+    pub shannon_diversity: f64,
+    /// Genome lengths in bases
+    pub lengths: LengthDistribution,
+    pub num_species: usize,
+    /// The largest species first. Callers can truncate this to show
+    /// only the biggest ones.
+    pub species: Vec<Species>,
+    /// The generation of the youngest creature in the population,
+    /// which species' ages are measured against
+    pub newest_generation: usize,
+}
+
+impl DiversityReport {
+    /// How many species are shown when a report is printed during a
+    /// simulation
+    pub const SHOWN_SPECIES: usize = 5;
+    /// How many situations behaviours are tried in before they're
+    /// compared properly, and how many thoughts into their cycles
+    const PROBES: usize = 16;
+    const PROBED_THOUGHTS: usize = 4;
+}
+
+/// Looks at every creature in the population. Grouping creatures by
+/// behaviour is linear in the population size, but behaviours that
+/// decide the same in every probe situation are compared with each
+/// other properly, which is quadratic in how many of them there are.
+/// That's few enough in practice to do once per checkpoint.
+pub fn analyze(population: &Creatures) -> DiversityReport {
+    let mut genomes: HashMap<u32, usize> = HashMap::new();
+    let mut lengths: Vec<usize> = Vec::with_capacity(population.len());
+    let mut species: HashMap<Vec<&ast::Condition>, (&Creature, Species)> =
+        HashMap::new();
+    let mut newest_generation = 0;
+    for creature in population.iter() {
+        *genomes.entry(creature.dna().hash()).or_insert(0) += 1;
+        lengths.push(creature.dna().len());
+        newest_generation = newest_generation.max(creature.generation);
+        species
+            .entry(behaviour(creature))
+            .or_insert_with(|| {
+                let species = Species {
+                    size: 0,
+                    oldest_generation: creature.generation,
+                    newest_generation: creature.generation,
+                    example: creature.id,
+                };
+                (creature, species)
+            })
+            .1
+            .add(creature);
+    }
+    let mut species = merge_equivalent(species.into_values().collect());
+    species.sort_by_key(|s| (Reverse(s.size), s.oldest_generation, s.example));
+    DiversityReport {
+        creatures: population.len(),
+        distinct_genomes: genomes.len(),
+        shannon_diversity: shannon_diversity(
+            &genomes.values().cloned().collect::<Vec<usize>>(),
+        ),
+        lengths: length_distribution(lengths),
+        num_species: species.len(),
+        species,
+        newest_generation,
+    }
+}
+
+/// What decides how a creature fights. Creatures with the same DNA
+/// always have the same behaviour, but different DNA can simplify to
+/// the same thing.
+fn behaviour(creature: &Creature) -> Vec<&ast::Condition> {
+    creature
+        .thoughts()
+        .iter()
+        .map(|decision| &decision.tree)
+        .collect()
+}
+
+/// What the first few thoughts of a creature's cycle decide in some
+/// fixed situations. Behaviours that always decide the same have the
+/// same signature.
+fn signature(
+    creature: &Creature,
+    probes: &[Situation],
+) -> Vec<PerformableAction> {
+    let thoughts = creature.thoughts();
+    (0..DiversityReport::PROBED_THOUGHTS)
+        .flat_map(|i| {
+            let tree = &thoughts[i % thoughts.len()].tree;
+            probes.iter().map(move |probe| probe.decide(tree))
+        })
+        .collect()
+}
+
+/// Creatures with differently written thoughts can still always
+/// decide the same, so groups of them that do are one species. Only
+/// groups with the same `signature` can be the same species, so each
+/// group is only compared with the species found so far that have
+/// its signature, biggest first.
+fn merge_equivalent(groups: Vec<(&Creature, Species)>) -> Vec<Species> {
+    let probes = Situation::probes(DiversityReport::PROBES);
+    let mut buckets: HashMap<Vec<PerformableAction>, Vec<_>> = HashMap::new();
+    for (creature, group) in groups {
+        buckets
+            .entry(signature(creature, &probes))
+            .or_default()
+            .push((creature, group));
+    }
+    buckets.into_values().flat_map(merge_bucket).collect()
+}
+
+/// Merges groups with the same signature that always decide the same
+fn merge_bucket(mut groups: Vec<(&Creature, Species)>) -> Vec<Species> {
+    groups.sort_by_key(|(_, s)| Reverse(s.size));
+    let mut merged: Vec<(&Creature, Species)> = Vec::new();
+    for (creature, group) in groups {
+        let same = merged.iter_mut().find(|&&mut (example, _)| {
+            simplify::equivalent(example.thoughts(), creature.thoughts())
+                .is_ok()
+        });
+        match same {
+            Some(&mut (_, ref mut species)) => species.merge(&group),
+            None => merged.push((creature, group)),
+        }
+    }
+    merged.into_iter().map(|(_, species)| species).collect()
+}
+
+impl Species {
+    fn add(&mut self, creature: &Creature) {
+        self.size += 1;
+        self.example = self.example.min(creature.id);
+        self.oldest_generation =
+            self.oldest_generation.min(creature.generation);
+        self.newest_generation =
+            self.newest_generation.max(creature.generation);
+    }
+
+    fn merge(&mut self, other: &Species) {
+        self.size += other.size;
+        self.example = self.example.min(other.example);
+        self.oldest_generation =
+            self.oldest_generation.min(other.oldest_generation);
+        self.newest_generation =
+            self.newest_generation.max(other.newest_generation);
+    }
+}
+
+fn shannon_diversity(counts: &[usize]) -> f64 {
+    let total: usize = counts.iter().sum();
+    counts
+        .iter()
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.ln()
+        })
+        .sum()
+}
+
+fn length_distribution(mut lengths: Vec<usize>) -> LengthDistribution {
+    if lengths.is_empty() {
+        return LengthDistribution::default();
+    }
+    lengths.sort();
+    LengthDistribution {
+        min: lengths[0],
+        median: lengths[lengths.len() / 2],
+        mean: lengths.iter().sum::<usize>() as f64 / lengths.len() as f64,
+        max: lengths[lengths.len() - 1],
+    }
+}
+
+impl fmt::Display for DiversityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} distinct genomes among {} creatures, Shannon diversity \
+             {:.3}",
+            self.distinct_genomes, self.creatures, self.shannon_diversity
+        )?;
+        writeln!(
+            f,
+            "Genome lengths: min {}, median {}, mean {:.1}, max {}",
+            self.lengths.min,
+            self.lengths.median,
+            self.lengths.mean,
+            self.lengths.max
+        )?;
+        writeln!(f, "{} species by behaviour", self.num_species)?;
+        for species in &self.species {
+            writeln!(
+                f,
+                "  {:>7} creatures, generations {}-{}, age {}, e.g. {}",
+                species.size,
+                species.oldest_generation,
+                species.newest_generation,
+                self.newest_generation - species.oldest_generation,
+                species.example
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assembly::assemble;
+    use rng::RngState;
+    use rules::Rules;
+
+    #[test]
+    fn creatures_that_always_decide_the_same_are_one_species() {
+        let rules = Rules::default();
+        let creature = |id: u64, assembly: &str| {
+            let dna = assemble(assembly).unwrap();
+            Creature::seeded(CreatureID::from(id), dna, &rules).unwrap()
+        };
+        let mut population = Creatures::new(0, RngState::from_seed(1), &rules);
+        population.absorb_all(vec![
+            creature(9, "less_than me energy target energy mate flee"),
+            creature(4, "always flee"),
+            creature(7, "less_than me energy target energy mate flee"),
+            creature(3, "greater_than target energy me energy mate flee"),
+        ]);
+        let report = analyze(&population);
+        assert_eq!(report.num_species, 2);
+        assert_eq!(report.species[0].size, 3);
+        assert_eq!(report.species[0].example, CreatureID::from(3));
+        assert_eq!(report.species[1].example, CreatureID::from(4));
+    }
+
+    #[test]
+    fn shannon_diversity_of_even_and_uneven_populations() {
+        assert_eq!(shannon_diversity(&[10]), 0.0);
+        let even = shannon_diversity(&[5, 5, 5, 5]);
+        assert!((even - 4f64.ln()).abs() < 1e-9);
+        assert!(shannon_diversity(&[17, 1, 1, 1]) < even);
+    }
+}
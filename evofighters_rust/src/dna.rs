@@ -166,6 +166,11 @@ impl DNA {
         self.0.len() * Gene::LENGTH
     }
 
+    /// All of the bases, in order
+    pub fn bases<'a>(&'a self) -> impl Iterator<Item = i8> + 'a {
+        self.0.iter().flat_map(|gene| gene.iter().cloned())
+    }
+
     pub fn base_stream(&self, offset: usize) -> DNAIter {
         DNAIter::new(self.clone(), offset)
     }
@@ -398,7 +403,7 @@ pub mod lex {
     }
 
     enum_from_primitive! {
-        #[derive(Ord, PartialOrd, Eq, PartialEq)]
+        #[derive(Ord, PartialOrd, Eq, PartialEq, Hash)]
         #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
         /// Attributes are parsed from `DNA`. When a `Value` requires looking
         /// at a fighter's attributes, this decides which one is selected
@@ -449,7 +454,7 @@ pub mod lex {
     }
 
     enum_from_primitive! {
-        #[derive(Ord, PartialOrd, Eq, PartialEq)]
+        #[derive(Ord, PartialOrd, Eq, PartialEq, Hash)]
         #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
         /// Parsed from `DNA`, this represents the color of a signal
         pub enum Signal {
@@ -464,7 +469,7 @@ pub mod lex {
     }
 
     enum_from_primitive! {
-        #[derive(Ord, PartialOrd, Eq, PartialEq)]
+        #[derive(Ord, PartialOrd, Eq, PartialEq, Hash)]
         #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
         /// Parsed from `DNA`, this represents a damage type
         pub enum DamageType {
@@ -477,6 +482,29 @@ pub mod lex {
             // pay attention to Gene::MAX_MEANINGFUL_VALUE if adding items
         }
     }
+
+    impl fmt::Display for DamageType {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                DamageType::Fire => write!(f, "fire"),
+                DamageType::Ice => write!(f, "ice"),
+                DamageType::Electricity => write!(f, "electricity"),
+            }
+        }
+    }
+
+    impl fmt::Display for Signal {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                Signal::Red => write!(f, "red"),
+                Signal::Yellow => write!(f, "yellow"),
+                Signal::Blue => write!(f, "blue"),
+                Signal::Purple => write!(f, "purple"),
+                Signal::Orange => write!(f, "orange"),
+                Signal::Green => write!(f, "green"),
+            }
+        }
+    }
 }
 
 /// The `ast` module is structured trees of conditions and actions
@@ -487,7 +515,7 @@ pub mod ast {
     use std::fmt;
     use dna::lex;
 
-    #[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize, Deserialize)]
+    #[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Serialize, Deserialize)]
     pub enum BinOp {
         LT,
         GT,
@@ -506,13 +534,13 @@ pub mod ast {
         }
     }
 
-    #[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize, Deserialize)]
+    #[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Serialize, Deserialize)]
     pub enum ActorType {
         Me,
         Other,
     }
 
-    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
     pub enum Condition {
         Always(Action),
         RangeCompare {
@@ -537,7 +565,7 @@ pub mod ast {
         },
     }
 
-    #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+    #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
     pub enum Value {
         Literal(u8),
         Random,
@@ -556,7 +584,7 @@ pub mod ast {
         }
     }
 
-    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
     pub enum Action {
         Subcondition(Box<Condition>),
         Attack(lex::DamageType),
@@ -568,4 +596,170 @@ pub mod ast {
         Wait,
         Flee,
     }
+
+    /// Conditions are displayed as nested if/else pseudo-code
+    impl fmt::Display for Condition {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.write_indented(f, 0)
+        }
+    }
+
+    impl Condition {
+        const INDENT: usize = 4;
+
+        fn write_indented(
+            &self,
+            f: &mut fmt::Formatter,
+            depth: usize,
+        ) -> fmt::Result {
+            let (affirmed, denied) = match *self {
+                Condition::Always(ref action) => {
+                    return action.write_indented(f, depth)
+                }
+                Condition::RangeCompare {
+                    ref value,
+                    ref bound_a,
+                    ref bound_b,
+                    ref affirmed,
+                    ref denied,
+                } => {
+                    writeln!(
+                        f,
+                        "{:indent$}if {} is between {} and {} {{",
+                        "",
+                        value,
+                        bound_a,
+                        bound_b,
+                        indent = depth * Condition::INDENT
+                    )?;
+                    (affirmed, denied)
+                }
+                Condition::BinCompare {
+                    ref operation,
+                    ref lhs,
+                    ref rhs,
+                    ref affirmed,
+                    ref denied,
+                } => {
+                    writeln!(
+                        f,
+                        "{:indent$}if {} is {} {} {{",
+                        "",
+                        lhs,
+                        operation,
+                        rhs,
+                        indent = depth * Condition::INDENT
+                    )?;
+                    (affirmed, denied)
+                }
+                Condition::ActionCompare {
+                    ref actor_type,
+                    ref action,
+                    ref affirmed,
+                    ref denied,
+                } => {
+                    let whose = match *actor_type {
+                        ActorType::Me => "my",
+                        ActorType::Other => "my target's",
+                    };
+                    if let Action::Subcondition(ref cond) = *action {
+                        // Quoting a whole if/else would be unreadable
+                        writeln!(
+                            f,
+                            "{:indent$}if {} last action was what this \
+                             decides: {{",
+                            "",
+                            whose,
+                            indent = depth * Condition::INDENT
+                        )?;
+                        cond.write_indented(f, depth + 1)?;
+                        writeln!(
+                            f,
+                            "{:indent$}}} then {{",
+                            "",
+                            indent = depth * Condition::INDENT
+                        )?;
+                    } else {
+                        writeln!(
+                            f,
+                            "{:indent$}if {} last action was \"{}\" {{",
+                            "",
+                            whose,
+                            action,
+                            indent = depth * Condition::INDENT
+                        )?;
+                    }
+                    (affirmed, denied)
+                }
+            };
+            affirmed.write_indented(f, depth + 1)?;
+            writeln!(
+                f,
+                "{:indent$}}} else {{",
+                "",
+                indent = depth * Condition::INDENT
+            )?;
+            denied.write_indented(f, depth + 1)?;
+            writeln!(f, "{:indent$}}}", "", indent = depth * Condition::INDENT)
+        }
+    }
+
+    impl fmt::Display for Action {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match *self {
+                Action::Subcondition(ref cond) => write!(f, "{}", cond),
+                Action::Attack(dmg) => write!(f, "attack with {}", dmg),
+                Action::Defend(dmg) => write!(f, "defend against {}", dmg),
+                Action::Signal(sig) => write!(f, "signal {}", sig),
+                Action::Eat => write!(f, "eat the top inventory item"),
+                Action::Take => write!(f, "take the target's top item"),
+                Action::Mate => write!(f, "mate with the target"),
+                Action::Wait => write!(f, "wait"),
+                Action::Flee => write!(f, "flee the encounter"),
+            }
+        }
+    }
+
+    impl Action {
+        fn write_indented(
+            &self,
+            f: &mut fmt::Formatter,
+            depth: usize,
+        ) -> fmt::Result {
+            match *self {
+                Action::Subcondition(ref cond) => cond.write_indented(f, depth),
+                ref action => writeln!(
+                    f,
+                    "{:indent$}{}",
+                    "",
+                    action,
+                    indent = depth * Condition::INDENT
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ast::{Action, BinOp, Condition, Value};
+    use super::lex::{Attribute, DamageType};
+
+    #[test]
+    fn conditions_display_as_pseudo_code() {
+        let cond = Condition::BinCompare {
+            operation: BinOp::LT,
+            lhs: Value::Me(Attribute::Energy),
+            rhs: Value::Literal(10),
+            affirmed: Action::Flee,
+            denied: Action::Subcondition(Box::new(Condition::Always(
+                Action::Attack(DamageType::Fire),
+            ))),
+        };
+        assert_eq!(
+            cond.to_string(),
+            "if my energy is less than 10 {\n    flee the encounter\n} \
+             else {\n    attack with fire\n}\n"
+        );
+    }
 }
@@ -6,7 +6,7 @@ use creatures::Creature;
 use rng::RngState;
 
 // PerformableAction is the result of evaluating a thought tree
-#[derive(Debug, Copy, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum PerformableAction {
     Attack(lex::DamageType),
     Defend(lex::DamageType),
@@ -0,0 +1,203 @@
+//! Keeps the most notable creatures of a run around after they die,
+//! so their DNA isn't lost when the population forgets them.
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use creatures::{Creature, CreatureID};
+use dna::DNA;
+use stats::{CreatureStats, Ranking};
+
+/// What's remembered about a creature in the hall of fame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Champion {
+    pub id: CreatureID,
+    pub generation: usize,
+    pub parents: (CreatureID, CreatureID),
+    pub stats: CreatureStats,
+    pub dna: DNA,
+}
+
+impl From<&Creature> for Champion {
+    fn from(creature: &Creature) -> Champion {
+        Champion {
+            id: creature.id,
+            generation: creature.generation,
+            parents: creature.parents,
+            stats: creature.stats,
+            dna: creature.dna().clone(),
+        }
+    }
+}
+
+impl fmt::Display for Champion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[Creature {}] gen: {} kills: {} children: {} survived: {}",
+            self.id,
+            self.generation,
+            self.stats.kills,
+            self.stats.num_children,
+            self.stats.survived
+        )
+    }
+}
+
+/// The top creatures by each `Ranking`. A creature can be in more
+/// than one list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HallOfFame {
+    /// How many creatures to keep for each ranking
+    size: usize,
+    kills: Vec<Champion>,
+    children: Vec<Champion>,
+    survived: Vec<Champion>,
+}
+
+impl Default for HallOfFame {
+    fn default() -> HallOfFame {
+        HallOfFame::new(HallOfFame::DEFAULT_SIZE)
+    }
+}
+
+impl HallOfFame {
+    pub const DEFAULT_SIZE: usize = 10;
+
+    pub fn new(size: usize) -> HallOfFame {
+        HallOfFame {
+            size,
+            kills: Vec::with_capacity(size + 1),
+            children: Vec::with_capacity(size + 1),
+            survived: Vec::with_capacity(size + 1),
+        }
+    }
+
+    /// The champions for a ranking, best first
+    pub fn champions(&self, ranking: Ranking) -> &[Champion] {
+        match ranking {
+            Ranking::Kills => &self.kills,
+            Ranking::Children => &self.children,
+            Ranking::Survived => &self.survived,
+        }
+    }
+
+    fn champions_mut(&mut self, ranking: Ranking) -> &mut Vec<Champion> {
+        match ranking {
+            Ranking::Kills => &mut self.kills,
+            Ranking::Children => &mut self.children,
+            Ranking::Survived => &mut self.survived,
+        }
+    }
+
+    /// Every champion once, even if they're in several lists
+    pub fn all_champions(&self) -> Vec<&Champion> {
+        let mut all: Vec<&Champion> = Vec::new();
+        for &ranking in &Ranking::ALL {
+            for champion in self.champions(ranking) {
+                if all.iter().all(|other| other.id != champion.id) {
+                    all.push(champion);
+                }
+            }
+        }
+        all
+    }
+
+    /// Enters a creature into the hall of fame if it's better than
+    /// anyone already there. This is called for every creature that
+    /// dies, so it checks cheaply before copying the creature.
+    pub fn consider(&mut self, creature: &Creature) {
+        if creature.is_feeder() {
+            return;
+        }
+        for &ranking in &Ranking::ALL {
+            if self.deserves_entry(ranking, &creature.stats) {
+                self.induct(ranking, Champion::from(creature));
+            }
+        }
+    }
+
+    /// Merges in the champions from another hall of fame, e.g. one
+    /// from a worker thread
+    pub fn absorb(&mut self, other: HallOfFame) {
+        for &ranking in &Ranking::ALL {
+            for champion in other.champions(ranking) {
+                if self.deserves_entry(ranking, &champion.stats) {
+                    self.induct(ranking, champion.clone());
+                }
+            }
+        }
+    }
+
+    fn deserves_entry(&self, ranking: Ranking, stats: &CreatureStats) -> bool {
+        let score = ranking.score(stats);
+        let champions = self.champions(ranking);
+        score > 0
+            && (champions.len() < self.size
+                || champions
+                    .last()
+                    .is_none_or(|last| score > ranking.score(&last.stats)))
+    }
+
+    fn induct(&mut self, ranking: Ranking, champion: Champion) {
+        let size = self.size;
+        let champions = self.champions_mut(ranking);
+        // The same creature can be considered again with better
+        // stats, e.g. if it's in two merged halls of fame
+        if let Some(index) = champions.iter().position(|c| c.id == champion.id)
+        {
+            if ranking.score(&champions[index].stats)
+                >= ranking.score(&champion.stats)
+            {
+                return;
+            }
+            champions.remove(index);
+        }
+        champions.push(champion);
+        champions.sort_by_key(|c| Reverse(ranking.score(&c.stats)));
+        champions.truncate(size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rules::Rules;
+
+    fn creature_with_kills(id: &str, kills: usize) -> Creature {
+        let mut creature =
+            Creature::seed_creature(id.parse().unwrap(), &Rules::default());
+        creature.stats.kills = kills;
+        creature
+    }
+
+    fn kill_counts(hall: &HallOfFame) -> Vec<usize> {
+        hall.champions(Ranking::Kills)
+            .iter()
+            .map(|c| c.stats.kills)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_only_the_best() {
+        let mut hall = HallOfFame::new(3);
+        for (id, &kills) in [4, 1, 7, 0, 5, 2].iter().enumerate() {
+            hall.consider(&creature_with_kills(&(id + 1).to_string(), kills));
+        }
+        assert_eq!(kill_counts(&hall), vec![7, 5, 4]);
+        assert!(hall.champions(Ranking::Children).is_empty());
+    }
+
+    #[test]
+    fn absorbing_doesnt_duplicate_champions() {
+        let mut hall = HallOfFame::new(3);
+        hall.consider(&creature_with_kills("1", 2));
+        let mut other = HallOfFame::new(3);
+        other.consider(&creature_with_kills("1", 3));
+        other.consider(&creature_with_kills("2", 1));
+        hall.absorb(other);
+        assert_eq!(kill_counts(&hall), vec![3, 1]);
+        assert_eq!(hall.all_champions().len(), 2);
+    }
+}
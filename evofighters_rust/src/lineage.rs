@@ -0,0 +1,375 @@
+//! Records who every creature's parents were, so the family tree of
+//! a population can be drawn long after its ancestors have died.
+//!
+//! Births are appended to a log next to the save file, written
+//! whenever a checkpoint is. Each birth is a fixed size record, since
+//! a long run has hundreds of millions of them.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufWriter, ErrorKind, Read, Seek, SeekFrom};
+use std::io::Write as IoWrite;
+
+use creatures::{Creature, CreatureID};
+
+/// A single birth, as it's kept in the lineage log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BirthRecord {
+    pub id: CreatureID,
+    pub parents: (CreatureID, CreatureID),
+    pub generation: u32,
+    pub dna_hash: u32,
+    /// The worker thread the creature was born on, which is always 0
+    /// in runs without threads
+    pub worker: u16,
+    /// The number of the event the creature was born in. Each worker
+    /// counts its own events, so only births on the same worker have
+    /// different event numbers.
+    pub event: u64,
+}
+
+impl BirthRecord {
+    /// Size of a record in the log, in bytes
+    const SIZE: usize = 40;
+
+    pub fn new(child: &Creature, worker: u16, event: u64) -> BirthRecord {
+        BirthRecord {
+            id: child.id,
+            parents: child.parents,
+            generation: child.generation as u32,
+            dna_hash: child.dna().hash(),
+            worker,
+            event,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; BirthRecord::SIZE] {
+        let mut bytes = [0; BirthRecord::SIZE];
+        write_le(&mut bytes[0..8], self.id.as_u64());
+        write_le(&mut bytes[8..16], self.parents.0.as_u64());
+        write_le(&mut bytes[16..24], self.parents.1.as_u64());
+        write_le(&mut bytes[24..30], self.event);
+        write_le(&mut bytes[30..32], u64::from(self.worker));
+        write_le(&mut bytes[32..36], u64::from(self.generation));
+        write_le(&mut bytes[36..40], u64::from(self.dna_hash));
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> BirthRecord {
+        BirthRecord {
+            id: CreatureID::from(read_le(&bytes[0..8])),
+            parents: (
+                CreatureID::from(read_le(&bytes[8..16])),
+                CreatureID::from(read_le(&bytes[16..24])),
+            ),
+            event: read_le(&bytes[24..30]),
+            worker: read_le(&bytes[30..32]) as u16,
+            generation: read_le(&bytes[32..36]) as u32,
+            dna_hash: read_le(&bytes[36..40]) as u32,
+        }
+    }
+}
+
+/// Writes the low `bytes.len()` bytes of `value`, least significant
+/// first
+fn write_le(bytes: &mut [u8], value: u64) {
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (value >> (8 * i)) as u8;
+    }
+}
+
+fn read_le(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .enumerate()
+        .fold(0, |value, (i, &byte)| value | u64::from(byte) << (8 * i))
+}
+
+/// The append-only log of births that goes with a save file
+pub struct LineageLog {
+    filename: String,
+}
+
+impl LineageLog {
+    pub fn for_savefile(savefile: &str) -> LineageLog {
+        LineageLog {
+            filename: format!("{}.lineage", savefile),
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn append(&self, births: &[BirthRecord]) -> io::Result<()> {
+        if births.is_empty() {
+            return Ok(());
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.filename)?;
+        let mut writer = BufWriter::new(file);
+        for birth in births {
+            writer.write_all(&birth.to_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Drops the births after a checkpoint saved `events` events into
+    /// the run, which a crash or a checkpoint restored from a backup
+    /// leaves behind, along with any record cut short by a crash.
+    /// Returns how many births were dropped.
+    ///
+    /// Births are appended a checkpoint at a time, and each has a lower
+    /// event number than the number of events before the checkpoint
+    /// it was saved with, so the births to drop are all at the end.
+    pub fn truncate_after(&self, events: u64) -> io::Result<usize> {
+        let mut file =
+            match OpenOptions::new().read(true).write(true).open(&self.filename)
+            {
+                Ok(file) => file,
+                Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+                Err(e) => return Err(e),
+            };
+        let size = BirthRecord::SIZE as u64;
+        let records = file.metadata()?.len() / size;
+        let mut kept = records;
+        let mut bytes = [0; BirthRecord::SIZE];
+        while kept > 0 {
+            file.seek(SeekFrom::Start((kept - 1) * size))?;
+            file.read_exact(&mut bytes)?;
+            if BirthRecord::from_bytes(&bytes).event < events {
+                break;
+            }
+            kept -= 1;
+        }
+        file.set_len(kept * size)?;
+        Ok((records - kept) as usize)
+    }
+
+    /// Reads every birth in the log. A record cut short by a crash
+    /// while appending is ignored.
+    pub fn read_all(&self) -> io::Result<Vec<BirthRecord>> {
+        let mut bytes = Vec::new();
+        File::open(&self.filename)?.read_to_end(&mut bytes)?;
+        Ok(bytes
+            .chunks(BirthRecord::SIZE)
+            .filter(|chunk| chunk.len() == BirthRecord::SIZE)
+            .map(BirthRecord::from_bytes)
+            .collect())
+    }
+}
+
+/// The family tree of everything in a lineage log
+pub struct Lineage {
+    births: HashMap<CreatureID, BirthRecord>,
+}
+
+impl Lineage {
+    pub fn new(births: Vec<BirthRecord>) -> Lineage {
+        Lineage {
+            births: births.into_iter().map(|birth| (birth.id, birth)).collect(),
+        }
+    }
+
+    /// The given creatures and all of their ancestors. Creatures with
+    /// no birth record (like the seed population) have no ancestors.
+    pub fn ancestry(&self, ids: &[CreatureID]) -> BTreeSet<CreatureID> {
+        let mut ancestry = BTreeSet::new();
+        let mut to_visit = ids.to_vec();
+        while let Some(id) = to_visit.pop() {
+            if id.is_feeder() || !ancestry.insert(id) {
+                continue;
+            }
+            if let Some(birth) = self.births.get(&id) {
+                to_visit.push(birth.parents.0);
+                to_visit.push(birth.parents.1);
+            }
+        }
+        ancestry
+    }
+
+    /// Graphviz DOT showing both parents of each creature in the
+    /// ancestry of `ids`, with `ids` themselves filled in
+    pub fn to_dot(&self, ids: &[CreatureID]) -> String {
+        let ancestry = self.ancestry(ids);
+        let mut dot = String::from("digraph lineage {\n");
+        for &id in &ancestry {
+            let style = if ids.contains(&id) { ", style=filled" } else { "" };
+            match self.births.get(&id) {
+                Some(birth) => writeln!(
+                    dot,
+                    "  c{} [label=\"{}\\ngen {}\"{}];",
+                    id, id, birth.generation, style
+                ),
+                None => writeln!(dot, "  c{} [label=\"{}\"{}];", id, id, style),
+            }.unwrap();
+        }
+        for &id in &ancestry {
+            if let Some(birth) = self.births.get(&id) {
+                for parent in &[birth.parents.0, birth.parents.1] {
+                    if !parent.is_feeder() {
+                        writeln!(dot, "  c{} -> c{};", parent, id).unwrap();
+                    }
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// A Newick tree of the ancestry of `ids`. Newick can only give
+    /// each node one parent, so creatures are placed under their
+    /// first parent. Branch lengths are the number of events between
+    /// a parent's birth and its child's, where both are known.
+    pub fn to_newick(&self, ids: &[CreatureID]) -> String {
+        let ancestry = self.ancestry(ids);
+        let mut children: BTreeMap<CreatureID, Vec<CreatureID>> =
+            BTreeMap::new();
+        let mut roots = Vec::new();
+        for &id in &ancestry {
+            match self.tree_parent(id) {
+                Some(parent) if ancestry.contains(&parent) => {
+                    children.entry(parent).or_default().push(id)
+                }
+                _ => roots.push(id),
+            }
+        }
+
+        enum Step {
+            Visit(CreatureID),
+            Comma,
+            Close(Option<CreatureID>),
+        }
+        // Deep family trees would overflow the stack if this recursed
+        let mut stack = Vec::new();
+        let push_children =
+            |stack: &mut Vec<Step>, parent, kids: &[CreatureID]| {
+                stack.push(Step::Close(parent));
+                for (i, &kid) in kids.iter().enumerate().rev() {
+                    stack.push(Step::Visit(kid));
+                    if i > 0 {
+                        stack.push(Step::Comma);
+                    }
+                }
+            };
+        let mut newick = String::new();
+        if roots.len() == 1 {
+            stack.push(Step::Visit(roots[0]));
+        } else {
+            newick.push('(');
+            push_children(&mut stack, None, &roots);
+        }
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Visit(id) => match children.get(&id) {
+                    Some(kids) => {
+                        newick.push('(');
+                        push_children(&mut stack, Some(id), kids);
+                    }
+                    None => self.write_newick_label(&mut newick, id),
+                },
+                Step::Comma => newick.push(','),
+                Step::Close(id) => {
+                    newick.push(')');
+                    if let Some(id) = id {
+                        self.write_newick_label(&mut newick, id);
+                    }
+                }
+            }
+        }
+        newick.push_str(";\n");
+        newick
+    }
+
+    fn tree_parent(&self, id: CreatureID) -> Option<CreatureID> {
+        self.births
+            .get(&id)
+            .map(|birth| birth.parents.0)
+            .filter(|parent| !parent.is_feeder())
+    }
+
+    fn write_newick_label(&self, newick: &mut String, id: CreatureID) {
+        write!(newick, "{}", id).unwrap();
+        let birth_events = self.tree_parent(id).and_then(|parent| {
+            Some((self.births.get(&parent)?.event, self.births[&id].event))
+        });
+        if let Some((parent_event, event)) = birth_events {
+            write!(newick, ":{}", event.saturating_sub(parent_event))
+                .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn birth(id: u64, parents: (u64, u64), event: u64) -> BirthRecord {
+        BirthRecord {
+            id: CreatureID::from(id),
+            parents: (CreatureID::from(parents.0), CreatureID::from(parents.1)),
+            generation: 1,
+            dna_hash: 0xdead_beef,
+            worker: 0,
+            event,
+        }
+    }
+
+    #[test]
+    fn records_survive_a_round_trip() {
+        let record = BirthRecord {
+            generation: 70_000,
+            worker: 11,
+            ..birth(1 << 40, (3, u64::MAX), 12_345_678_901)
+        };
+        assert_eq!(BirthRecord::from_bytes(&record.to_bytes()), record);
+    }
+
+    #[test]
+    fn births_after_the_checkpoint_are_dropped() {
+        let path = env::temp_dir().join("evofighters_truncate_test.evo");
+        let log = LineageLog::for_savefile(path.to_str().unwrap());
+        // Two checkpoints' worth of births from two workers, the first
+        // saved 20 events in and the second 40 events in
+        let births: Vec<BirthRecord> = [(2, 5), (3, 12), (4, 20), (5, 33)]
+            .iter()
+            .map(|&(id, event)| BirthRecord {
+                worker: (id % 2) as u16,
+                ..birth(id, (1, 1), event)
+            })
+            .collect();
+        log.append(&births).unwrap();
+        // and half of a record from a crash while appending
+        OpenOptions::new()
+            .append(true)
+            .open(log.filename())
+            .unwrap()
+            .write_all(&[1; 10])
+            .unwrap();
+        assert_eq!(log.truncate_after(40).unwrap(), 0);
+        assert_eq!(log.read_all().unwrap(), births);
+        assert_eq!(log.truncate_after(20).unwrap(), 2);
+        assert_eq!(log.read_all().unwrap(), &births[..2]);
+        fs::remove_file(log.filename()).unwrap();
+        assert_eq!(log.truncate_after(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn newick_follows_first_parents() {
+        // 1, 2 and 3 are seed creatures
+        let lineage = Lineage::new(vec![
+            birth(4, (1, 2), 10),
+            birth(5, (4, 3), 25),
+            birth(6, (1, 3), 30),
+        ]);
+        let ids = [CreatureID::from(5), CreatureID::from(6)];
+        assert_eq!(lineage.ancestry(&ids).len(), 6);
+        assert_eq!(lineage.to_newick(&ids), "(((5:15)4,6)1,2,3);\n");
+    }
+}
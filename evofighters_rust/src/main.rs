@@ -1,7 +1,10 @@
 #![feature(box_patterns)]
 #![feature(nll)]
+#![cfg_attr(test, feature(test))]
 
+extern crate bincode;
 extern crate clap;
+extern crate ctrlc;
 #[macro_use]
 extern crate derive_builder;
 #[macro_use]
@@ -13,24 +16,40 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+extern crate termion;
 extern crate time;
 extern crate twox_hash;
 extern crate xz2;
 
+#[cfg(test)]
+extern crate test;
+
 #[macro_use]
 mod util;
 
 mod arena;
+mod assembly;
+mod bytecode;
 mod cli;
 mod creatures;
+mod dashboard;
+mod diversity;
 mod dna;
 mod eval;
+mod hall_of_fame;
+mod lineage;
+mod metrics;
 mod parsing;
 mod rng;
+mod rules;
 mod saver;
+mod seed_dna;
+mod shell;
 mod stats;
 mod sim;
 mod simplify;
+mod tournament;
+mod tui;
 
 fn main() {
     let app = cli::parse_args();
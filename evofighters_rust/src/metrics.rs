@@ -0,0 +1,164 @@
+//! A time series of how a run is going, appended to a file at
+//! `metric_fps` so runs can be plotted afterwards.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{BufWriter, Write};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json;
+
+use stats::GlobalStatistics;
+
+/// One line of the metrics log. The statistics are totals for the
+/// whole run, not just since the last sample.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Sample {
+    /// Seconds since the Unix epoch
+    pub timestamp: f64,
+    pub events: u64,
+    pub population: usize,
+    pub feeders: usize,
+    pub mutations: usize,
+    pub births: usize,
+    pub kills: usize,
+    pub feeders_eaten: usize,
+    pub rounds: usize,
+    pub events_per_second: u64,
+}
+
+impl Sample {
+    const CSV_HEADER: &'static str = "timestamp,events,population,feeders,\
+                                      mutations,births,kills,\
+                                      feeders_eaten,rounds,\
+                                      events_per_second";
+
+    pub fn new(
+        population: usize,
+        feeders: usize,
+        stats: &GlobalStatistics,
+        events_per_second: u64,
+    ) -> Sample {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("The clock is set before 1970");
+        Sample {
+            timestamp: now.as_secs() as f64
+                + f64::from(now.subsec_nanos()) / 1_000_000_000.0,
+            events: stats.events,
+            population,
+            feeders,
+            mutations: stats.mutations,
+            births: stats.children_born,
+            kills: stats.kills,
+            feeders_eaten: stats.feeders_eaten,
+            rounds: stats.rounds,
+            events_per_second,
+        }
+    }
+
+    fn to_csv(self) -> String {
+        format!(
+            "{:.3},{},{},{},{},{},{},{},{},{}",
+            self.timestamp,
+            self.events,
+            self.population,
+            self.feeders,
+            self.mutations,
+            self.births,
+            self.kills,
+            self.feeders_eaten,
+            self.rounds,
+            self.events_per_second
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsFormat {
+    JsonLines,
+    Csv,
+}
+
+impl MetricsFormat {
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            MetricsFormat::JsonLines => "jsonl",
+            MetricsFormat::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for MetricsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MetricsFormat, String> {
+        match s {
+            "jsonl" => Ok(MetricsFormat::JsonLines),
+            "csv" => Ok(MetricsFormat::Csv),
+            _ => Err(format!("{} isn't a metrics format", s)),
+        }
+    }
+}
+
+/// Appends samples to a metrics file, so a resumed run carries on
+/// the same time series
+pub struct MetricsLog {
+    writer: BufWriter<File>,
+    format: MetricsFormat,
+}
+
+impl MetricsLog {
+    /// Where the metrics for a save file go if no file is given
+    pub fn default_filename(savefile: &str, format: MetricsFormat) -> String {
+        format!("{}.metrics.{}", savefile, format.extension())
+    }
+
+    pub fn open(
+        filename: &str,
+        format: MetricsFormat,
+    ) -> io::Result<MetricsLog> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filename)?;
+        let is_new = file.metadata()?.len() == 0;
+        let mut log = MetricsLog {
+            writer: BufWriter::new(file),
+            format,
+        };
+        if is_new && format == MetricsFormat::Csv {
+            writeln!(log.writer, "{}", Sample::CSV_HEADER)?;
+        }
+        Ok(log)
+    }
+
+    /// Writes a sample and flushes it, so the log can be watched
+    /// while the run is going
+    pub fn record(&mut self, sample: &Sample) -> io::Result<()> {
+        match self.format {
+            MetricsFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, sample)?;
+                writeln!(self.writer)?;
+            }
+            MetricsFormat::Csv => writeln!(self.writer, "{}", sample.to_csv())?,
+        }
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_match_the_header() {
+        let sample = Sample::new(10, 3, &GlobalStatistics::new(), 500);
+        assert_eq!(
+            sample.to_csv().split(',').count(),
+            Sample::CSV_HEADER.split(',').count()
+        );
+        assert!(sample.to_csv().ends_with(",10,3,0,0,0,0,0,500"));
+    }
+}
@@ -1,30 +1,88 @@
 use std::iter::Iterator;
 use std::option::Option::*;
 use std::convert::From;
+use std::fmt;
+use std::mem;
+use std::sync::OnceLock;
 use num::FromPrimitive;
 
+use bytecode::Program;
 use dna::{ast, lex, DNAIter, DNA};
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Failure {
     DNAEmpty,
-    TookTooLong,
-    ParseTreeTooDeep,
+    /// Ran out of thinking steps while looking for `expected`, giving
+    /// up at the base at `offset`
+    TookTooLong { offset: usize, expected: Symbol },
+    /// Went more than `Parser::MAX_TREE_DEPTH` subconditions deep,
+    /// before reading the base at `offset`
+    ParseTreeTooDeep { offset: usize },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Failure::DNAEmpty => {
+                write!(f, "the DNA is empty or has a gene of stop codons")
+            }
+            Failure::TookTooLong { offset, expected } => write!(
+                f,
+                "took too long, still looking for {} at base {}",
+                expected.with_article(),
+                offset
+            ),
+            Failure::ParseTreeTooDeep { offset } => write!(
+                f,
+                "more than {} subconditions deep at base {}",
+                Parser::MAX_TREE_DEPTH,
+                offset
+            ),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Decision {
     pub tree: ast::Condition,
+    /// `tree` compiled, which is what's run in fights. Most parsed
+    /// decisions are thrown away or simplified first, so it's only
+    /// compiled once something asks for it.
+    program: OnceLock<Program>,
     pub offset: usize,
     pub icount: usize,
     pub skipped: usize,
 }
 
+impl Decision {
+    pub fn new(
+        tree: ast::Condition,
+        offset: usize,
+        icount: usize,
+        skipped: usize,
+    ) -> Decision {
+        Decision {
+            tree,
+            program: OnceLock::new(),
+            offset,
+            icount,
+            skipped,
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        self.program.get_or_init(|| Program::compile(&self.tree))
+    }
+}
+
 pub struct Indecision {
     pub reason: Failure,
     pub icount: usize,
     pub skipped: usize,
     pub offset: usize,
+    /// As much of the tree as was parsed before giving up, if the
+    /// parser was tracing
+    pub partial: Option<PartialTree>,
 }
 
 impl From<Indecision> for Failure {
@@ -40,12 +98,12 @@ pub enum Thought {
 
 impl Thought {
     fn feeder_decision() -> Thought {
-        Thought::Dec(Decision {
-            tree: ast::Condition::Always(ast::Action::Wait),
-            icount: 0,
-            skipped: Parser::MAX_THINKING_STEPS + 1,
-            offset: 0,
-        })
+        Thought::Dec(Decision::new(
+            ast::Condition::Always(ast::Action::Wait),
+            0,
+            0,
+            Parser::MAX_THINKING_STEPS + 1,
+        ))
     }
 
     pub fn offset(&self) -> usize {
@@ -65,6 +123,135 @@ impl Thought {
 
 type ParseResult<T> = Result<T, Failure>;
 
+/// The part of the grammar the parser expected a base to be
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Symbol {
+    Condition,
+    Value,
+    Action,
+    Attribute,
+    Literal,
+    DamageType,
+    Signal,
+}
+
+impl Symbol {
+    /// "a condition", "an action" and so on, for messages
+    pub fn with_article(self) -> String {
+        let name = self.to_string();
+        let article = if name.starts_with(|c| "aeiou".contains(c)) {
+            "an"
+        } else {
+            "a"
+        };
+        format!("{} {}", article, name)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Symbol::Condition => write!(f, "condition"),
+            Symbol::Value => write!(f, "value"),
+            Symbol::Action => write!(f, "action"),
+            Symbol::Attribute => write!(f, "attribute"),
+            Symbol::Literal => write!(f, "literal"),
+            Symbol::DamageType => write!(f, "damage type"),
+            Symbol::Signal => write!(f, "signal"),
+        }
+    }
+}
+
+/// A base read by a tracing parser
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct TracedBase {
+    pub offset: usize,
+    pub base: i8,
+    pub expected: Symbol,
+    /// How many subconditions deep the parser was
+    pub depth: usize,
+    /// Bases that don't mean anything as the expected symbol are
+    /// skipped
+    pub used: bool,
+}
+
+/// A parse tree that may have been cut short. Each node is a base
+/// that was used, and any symbols the parser didn't get as far as
+/// finding are left without a base.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PartialTree {
+    pub symbol: Symbol,
+    pub base: Option<TracedBase>,
+    pub children: Vec<PartialTree>,
+}
+
+impl PartialTree {
+    /// Rebuilds the tree from the bases a tracing parser used, which
+    /// are the nodes in the order they were parsed
+    fn from_trace(trace: &[TracedBase]) -> PartialTree {
+        PartialTree::build(
+            Symbol::Condition,
+            &mut trace.iter().filter(|traced| traced.used),
+        )
+    }
+
+    fn build<'a, I: Iterator<Item = &'a TracedBase>>(
+        symbol: Symbol,
+        used: &mut I,
+    ) -> PartialTree {
+        let base = used.next().cloned();
+        let children = match base {
+            Some(traced) => PartialTree::children(symbol, traced.base)
+                .iter()
+                .map(|&child| PartialTree::build(child, used))
+                .collect(),
+            None => Vec::new(),
+        };
+        PartialTree {
+            symbol,
+            base,
+            children,
+        }
+    }
+
+    /// What the grammar expects to follow `base` when it's read as
+    /// `symbol`
+    fn children(symbol: Symbol, base: i8) -> &'static [Symbol] {
+        const VALUE: Symbol = Symbol::Value;
+        const ACTION: Symbol = Symbol::Action;
+        match symbol {
+            Symbol::Condition => match lex::Condition::from_i8(base) {
+                Some(lex::Condition::Always) => &[ACTION],
+                Some(lex::Condition::InRange) => {
+                    &[VALUE, VALUE, VALUE, ACTION, ACTION]
+                }
+                Some(lex::Condition::MyLastAction)
+                | Some(lex::Condition::OtherLastAction) => {
+                    &[ACTION, ACTION, ACTION]
+                }
+                Some(_) => &[VALUE, VALUE, ACTION, ACTION],
+                None => &[],
+            },
+            Symbol::Action => match lex::Action::from_i8(base) {
+                Some(lex::Action::Subcondition) => &[Symbol::Condition],
+                Some(lex::Action::Attack) | Some(lex::Action::Defend) => {
+                    &[Symbol::DamageType]
+                }
+                Some(lex::Action::Signal) => &[Symbol::Signal],
+                _ => &[],
+            },
+            Symbol::Value => match lex::Value::from_i8(base) {
+                Some(lex::Value::Literal) => &[Symbol::Literal],
+                Some(lex::Value::Me) | Some(lex::Value::Other) => {
+                    &[Symbol::Attribute]
+                }
+                _ => &[],
+            },
+            _ => &[],
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Parser {
     icount: usize,
@@ -72,6 +259,7 @@ pub struct Parser {
     depth: usize,
     for_feeder: bool,
     dna_stream: DNAIter,
+    trace: Option<Vec<TracedBase>>,
 }
 
 impl Parser {
@@ -88,6 +276,23 @@ impl Parser {
             dna_stream: dna.base_stream(offset),
             depth: 0,
             for_feeder: false,
+            trace: None,
+        }
+    }
+
+    /// Records every base read from now on, to be collected with
+    /// `take_trace`. It's slower, so it's only for showing people how
+    /// DNA is read.
+    pub fn traced(mut self) -> Parser {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    /// The bases read since the trace was last taken
+    pub fn take_trace(&mut self) -> Vec<TracedBase> {
+        match self.trace {
+            Some(ref mut trace) => mem::take(trace),
+            None => Vec::new(),
         }
     }
 
@@ -95,26 +300,53 @@ impl Parser {
         self.dna_stream.offset()
     }
 
-    fn next_valid<T: FromPrimitive>(&mut self, minimum: i8) -> ParseResult<T> {
-        let mut next_i8 = self.dna_stream.next();
+    fn next_valid<T: FromPrimitive>(
+        &mut self,
+        expected: Symbol,
+        minimum: i8,
+    ) -> ParseResult<T> {
+        let mut next_i8 = self.read(expected);
         let mut next_val: Option<T> = next_i8.and_then(FromPrimitive::from_i8);
         self.icount += 1;
         while next_val.is_none() || next_i8.unwrap() < minimum {
-            next_i8 = self.dna_stream.next();
+            let offset = self.current_offset();
+            next_i8 = self.read(expected);
             next_val = next_i8.and_then(FromPrimitive::from_i8);
             self.skipped += 1;
             if self.icount + self.skipped > Parser::MAX_THINKING_STEPS {
-                return Err(Failure::TookTooLong);
+                return Err(Failure::TookTooLong { offset, expected });
+            }
+        }
+        if let Some(ref mut trace) = self.trace {
+            if let Some(traced) = trace.last_mut() {
+                traced.used = true;
             }
         }
         Ok(next_val.unwrap())
     }
 
+    fn read(&mut self, expected: Symbol) -> Option<i8> {
+        let offset = self.current_offset();
+        let base = self.dna_stream.next();
+        if let (Some(trace), Some(base)) = (self.trace.as_mut(), base) {
+            trace.push(TracedBase {
+                offset,
+                base,
+                expected,
+                depth: self.depth,
+                used: false,
+            });
+        }
+        base
+    }
+
     fn parse_condition(&mut self) -> ParseResult<ast::Condition> {
         if self.depth > Parser::MAX_TREE_DEPTH {
-            return Err(Failure::ParseTreeTooDeep);
+            return Err(Failure::ParseTreeTooDeep {
+                offset: self.current_offset(),
+            });
         }
-        Ok(match self.next_valid(0)? {
+        Ok(match self.next_valid(Symbol::Condition, 0)? {
             lex::Condition::Always => {
                 ast::Condition::Always(self.parse_action()?)
             }
@@ -160,7 +392,7 @@ impl Parser {
     }
 
     fn parse_action(&mut self) -> ParseResult<ast::Action> {
-        Ok(match self.next_valid(0)? {
+        Ok(match self.next_valid(Symbol::Action, 0)? {
             lex::Action::Subcondition => {
                 self.depth += 1;
                 let subcond = ast::Action::Subcondition(Box::new(
@@ -169,9 +401,15 @@ impl Parser {
                 self.depth -= 1;
                 subcond
             }
-            lex::Action::Attack => ast::Action::Attack(self.next_valid(0)?),
-            lex::Action::Defend => ast::Action::Defend(self.next_valid(0)?),
-            lex::Action::Signal => ast::Action::Signal(self.next_valid(0)?),
+            lex::Action::Attack => {
+                ast::Action::Attack(self.next_valid(Symbol::DamageType, 0)?)
+            }
+            lex::Action::Defend => {
+                ast::Action::Defend(self.next_valid(Symbol::DamageType, 0)?)
+            }
+            lex::Action::Signal => {
+                ast::Action::Signal(self.next_valid(Symbol::Signal, 0)?)
+            }
             lex::Action::Eat => ast::Action::Eat,
             lex::Action::Take => ast::Action::Take,
             lex::Action::Mate => ast::Action::Mate,
@@ -181,11 +419,17 @@ impl Parser {
     }
 
     fn parse_value(&mut self) -> ParseResult<ast::Value> {
-        Ok(match self.next_valid(0)? {
-            lex::Value::Literal => ast::Value::Literal(self.next_valid(0)?),
+        Ok(match self.next_valid(Symbol::Value, 0)? {
+            lex::Value::Literal => {
+                ast::Value::Literal(self.next_valid(Symbol::Literal, 0)?)
+            }
             lex::Value::Random => ast::Value::Random,
-            lex::Value::Me => ast::Value::Me(self.next_valid(0)?),
-            lex::Value::Other => ast::Value::Other(self.next_valid(0)?),
+            lex::Value::Me => {
+                ast::Value::Me(self.next_valid(Symbol::Attribute, 0)?)
+            }
+            lex::Value::Other => {
+                ast::Value::Other(self.next_valid(Symbol::Attribute, 0)?)
+            }
         })
     }
 }
@@ -196,19 +440,23 @@ impl Iterator for Parser {
         if self.for_feeder {
             return Some(Thought::feeder_decision());
         }
+        let trace_start = self.trace.as_ref().map_or(0, Vec::len);
         let value = Some(match self.parse_condition() {
             Err(msg) => Thought::Ind(Indecision {
                 icount: self.icount,
                 skipped: self.skipped,
                 reason: msg,
                 offset: self.current_offset(),
+                partial: self.trace.as_ref().map(|trace| {
+                    PartialTree::from_trace(&trace[trace_start..])
+                }),
             }),
-            Ok(tree) => Thought::Dec(Decision {
-                icount: self.icount,
-                skipped: self.skipped,
-                tree: tree,
-                offset: self.current_offset(),
-            }),
+            Ok(tree) => Thought::Dec(Decision::new(
+                tree,
+                self.current_offset(),
+                self.icount,
+                self.skipped,
+            )),
         });
         // Reset counts so the creatures get a new budget next time!
         self.icount = 0;
@@ -216,3 +464,63 @@ impl Iterator for Parser {
         value
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indecision(bases: Vec<i8>) -> Indecision {
+        let dna = DNA::from(bases);
+        match Parser::new(&dna, 0).traced().next().unwrap() {
+            Thought::Ind(indecision) => indecision,
+            Thought::Dec(decision) => panic!("Decided on {}", decision.tree),
+        }
+    }
+
+    #[test]
+    fn running_out_of_time_keeps_the_partial_tree() {
+        // in_range me energy, and then nothing but stop codons
+        let mut bases = vec![1, 2, 0];
+        bases.extend(vec![-1; 120]);
+        let indecision = indecision(bases);
+        assert_eq!(
+            indecision.reason,
+            Failure::TookTooLong {
+                offset: 100,
+                expected: Symbol::Value,
+            }
+        );
+        let partial = indecision.partial.unwrap();
+        let symbols: Vec<(Symbol, bool)> = partial
+            .children
+            .iter()
+            .map(|child| (child.symbol, child.base.is_some()))
+            .collect();
+        assert_eq!(
+            symbols,
+            vec![
+                (Symbol::Value, true),
+                (Symbol::Value, false),
+                (Symbol::Value, false),
+                (Symbol::Action, false),
+                (Symbol::Action, false),
+            ]
+        );
+        assert_eq!(partial.children[0].children[0].symbol, Symbol::Attribute);
+    }
+
+    #[test]
+    fn nesting_too_deep_says_where() {
+        // always subcondition, over and over
+        let indecision = indecision(vec![0; 50]);
+        assert_eq!(
+            indecision.reason,
+            Failure::ParseTreeTooDeep { offset: 42 }
+        );
+        assert!(indecision.partial.is_some());
+        // Only tracing parsers keep the tree
+        let dna = DNA::from(vec![0; 50]);
+        let untraced = Parser::new(&dna, 0).next().unwrap().into_result();
+        assert!(untraced.err().unwrap().partial.is_none());
+    }
+}
@@ -1,11 +1,43 @@
-use rand::{Rand, Rng, SeedableRng, XorShiftRng};
+use rand::{Rand, Rng};
 use rand::distributions;
 use rand::distributions::range::SampleRange;
 use creatures::{Creature, CreatureID};
 
-#[derive(Debug, Clone)]
+/// The same algorithm as `rand::XorShiftRng`, but its state can be
+/// saved in a checkpoint so a resumed simulation picks up exactly
+/// where it left off.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct XorShift {
+    x: u32,
+    y: u32,
+    z: u32,
+    w: u32,
+}
+
+impl XorShift {
+    fn from_seed([x, y, z, w]: [u32; 4]) -> XorShift {
+        assert!(
+            x != 0 || y != 0 || z != 0 || w != 0,
+            "XorShift::from_seed called with an all zero seed."
+        );
+        XorShift { x, y, z, w }
+    }
+}
+
+impl Rng for XorShift {
+    fn next_u32(&mut self) -> u32 {
+        let t = self.x ^ (self.x << 11);
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        self.w = self.w ^ (self.w >> 19) ^ (t ^ (t >> 8));
+        self.w
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RngState {
-    rng: XorShiftRng,
+    rng: XorShift,
 }
 
 impl Default for RngState {
@@ -17,10 +49,16 @@ impl Default for RngState {
 impl RngState {
     pub fn new(a: u32, b: u32, c: u32, d: u32) -> RngState {
         RngState {
-            rng: SeedableRng::from_seed([a, b, c, d]),
+            rng: XorShift::from_seed([a, b, c, d]),
         }
     }
 
+    /// Creates an rng from a user supplied seed, such as from the
+    /// command line. A seed of zero is the same as the default rng.
+    pub fn from_seed(seed: u64) -> RngState {
+        RngState::new(seed as u32 ^ 11, (seed >> 32) as u32 ^ 17, 23, 51)
+    }
+
     pub fn from_creatures(a: &Creature, b: &Creature) -> RngState {
         let a_p = CreatureID::parents_to_u32(a.parents);
         let b_p = CreatureID::parents_to_u32(b.parents);
@@ -61,3 +99,18 @@ impl RngState {
         self.rng.shuffle(values)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+
+    #[test]
+    fn xorshift_matches_rand_xorshift() {
+        let mut ours = XorShift::from_seed([11, 17, 23, 51]);
+        let mut theirs = XorShiftRng::from_seed([11, 17, 23, 51]);
+        for _ in 0..1000 {
+            assert_eq!(ours.next_u32(), theirs.next_u32());
+        }
+    }
+}
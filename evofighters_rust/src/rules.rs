@@ -0,0 +1,234 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+
+use serde_json;
+
+/// Rules are the game balance constants of the simulation. They can
+/// be loaded from a JSON rules file, and are saved in every checkpoint
+/// so it's always known which rules produced a population.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rules {
+    /// Version of the rules file format
+    pub version: u32,
+    /// Energy a creature is born with, and the most it can have
+    pub max_energy: usize,
+    /// Most items a creature can carry
+    pub max_inv_size: usize,
+    /// Item value a creature pays to mate, scaled by its mating share
+    pub mating_cost: usize,
+    /// Upper bound on the energy a creature gains by killing another
+    pub winner_life_bonus: usize,
+    pub combat: CombatRules,
+}
+
+/// The chances used in each round of a fight, depending on what each
+/// fighter is doing. Each entry is from the point of view of the
+/// fighter doing the first action in its name as `p1`. The reverse
+/// situations (e.g. mate vs. attack) use the same entry swapped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CombatRules {
+    /// Both fighters attack
+    pub attack_attack: Chances,
+    /// One fighter defends against the same damage type it's attacked with
+    pub attack_defend: Chances,
+    pub attack_mate: Chances,
+    /// One fighter attacks, the other does anything else, including
+    /// defending against the wrong damage type
+    pub attack_other: Chances,
+    pub defend_mate: Chances,
+    pub mate_mate: Chances,
+    /// One fighter tries to mate, the other does anything but attack,
+    /// defend or mate
+    pub mate_other: Chances,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Serialize, Deserialize)]
+pub struct CreatureChance {
+    pub chance_to_hit: usize,
+    pub dmg_multiplier: usize,
+    pub mating_share: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Serialize, Deserialize)]
+pub struct Chances {
+    pub chance_to_mate: usize,
+    pub p1: CreatureChance,
+    pub p2: CreatureChance,
+}
+
+impl Chances {
+    /// The same chances, with the fighters' roles reversed
+    pub fn swapped(self) -> Chances {
+        Chances {
+            chance_to_mate: self.chance_to_mate,
+            p1: self.p2,
+            p2: self.p1,
+        }
+    }
+
+    fn validate(&self, name: &str) -> Result<(), RulesError> {
+        let percentages = [
+            self.chance_to_mate,
+            self.p1.chance_to_hit,
+            self.p2.chance_to_hit,
+        ];
+        if percentages.iter().any(|&chance| chance > 100) {
+            Err(RulesError::Invalid(format!(
+                "chances in {} must be between 0 and 100",
+                name
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Shorthand for building the default combat rules
+fn chances(
+    chance_to_mate: usize,
+    (hit1, dmg1, share1): (usize, usize, usize),
+    (hit2, dmg2, share2): (usize, usize, usize),
+) -> Chances {
+    Chances {
+        chance_to_mate,
+        p1: CreatureChance {
+            chance_to_hit: hit1,
+            dmg_multiplier: dmg1,
+            mating_share: share1,
+        },
+        p2: CreatureChance {
+            chance_to_hit: hit2,
+            dmg_multiplier: dmg2,
+            mating_share: share2,
+        },
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            version: Rules::VERSION,
+            max_energy: 40,
+            max_inv_size: 3,
+            mating_cost: 3,
+            winner_life_bonus: 5,
+            combat: CombatRules {
+                attack_attack: chances(0, (75, 50, 0), (75, 50, 0)),
+                attack_defend: chances(0, (25, 25, 0), (25, 25, 0)),
+                attack_mate: chances(50, (50, 75, 70), (0, 0, 30)),
+                attack_other: chances(0, (100, 100, 0), (0, 0, 0)),
+                defend_mate: chances(25, (0, 0, 70), (0, 0, 30)),
+                mate_mate: chances(100, (0, 0, 50), (0, 0, 50)),
+                mate_other: chances(75, (0, 0, 0), (0, 0, 100)),
+            },
+        }
+    }
+}
+
+impl Rules {
+    pub const VERSION: u32 = 1;
+
+    /// Load and validate a rules file from disk
+    pub fn load(filename: &str) -> Result<Rules, RulesError> {
+        let rules: Rules = serde_json::from_reader(File::open(filename)?)?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    pub fn validate(&self) -> Result<(), RulesError> {
+        if self.version != Rules::VERSION {
+            return Err(RulesError::UnsupportedVersion(self.version));
+        }
+        if self.max_energy == 0 {
+            return Err(RulesError::Invalid(
+                "max_energy must be above 0".to_owned(),
+            ));
+        }
+        let CombatRules {
+            ref attack_attack,
+            ref attack_defend,
+            ref attack_mate,
+            ref attack_other,
+            ref defend_mate,
+            ref mate_mate,
+            ref mate_other,
+        } = self.combat;
+        attack_attack.validate("attack_attack")?;
+        attack_defend.validate("attack_defend")?;
+        attack_mate.validate("attack_mate")?;
+        attack_other.validate("attack_other")?;
+        defend_mate.validate("defend_mate")?;
+        mate_mate.validate("mate_mate")?;
+        mate_other.validate("mate_other")
+    }
+}
+
+#[derive(Debug)]
+pub enum RulesError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    UnsupportedVersion(u32),
+    Invalid(String),
+}
+
+impl From<io::Error> for RulesError {
+    fn from(err: io::Error) -> RulesError {
+        RulesError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for RulesError {
+    fn from(err: serde_json::Error) -> RulesError {
+        RulesError::Parse(err)
+    }
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RulesError::Io(ref err) => write!(f, "{}", err),
+            RulesError::Parse(ref err) => write!(f, "{}", err),
+            RulesError::UnsupportedVersion(version) => write!(
+                f,
+                "rules version {} isn't supported, expected version {}",
+                version,
+                Rules::VERSION
+            ),
+            RulesError::Invalid(ref msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_rules_file_matches_defaults() {
+        let example: Rules =
+            serde_json::from_str(include_str!("../rules.json")).unwrap();
+        assert_eq!(example, Rules::default());
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let rules = Rules {
+            version: 2,
+            ..Rules::default()
+        };
+        match rules.validate() {
+            Err(RulesError::UnsupportedVersion(2)) => (),
+            otherwise => panic!("Expected version error, got {:?}", otherwise),
+        }
+    }
+
+    #[test]
+    fn rejects_chances_above_100() {
+        let mut rules = Rules::default();
+        rules.combat.mate_mate.chance_to_mate = 101;
+        assert!(rules.validate().is_err());
+    }
+}
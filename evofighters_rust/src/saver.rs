@@ -1,15 +1,26 @@
+use std::fs;
 use std::fs::File;
-use std::io::Error;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Read,
+              Write};
+use std::path::Path;
+use std::str::FromStr;
 
+use bincode;
+use bincode::Options;
 use serde_json;
+use serde_json::Value;
 
 use xz2::write::XzEncoder;
 use xz2::read::XzDecoder;
 
-use creatures::{Creatures, DeserializableCreatures};
+use creatures::{Creatures, DeserializableCreatures, Island};
+use hall_of_fame::HallOfFame;
 use stats::GlobalStatistics;
+use rng::RngState;
+use rules::Rules;
 
 #[derive(Debug, Deserialize, Serialize, Copy, Clone, Builder)]
+#[serde(default)]
 pub struct Settings {
     #[builder(default = "0.10")]
     pub mutation_rate: f64,
@@ -19,6 +30,169 @@ pub struct Settings {
 
     #[builder(default = "30.0")]
     pub metric_fps: f64,
+
+    /// Whether damage types are strong or weak against each other
+    #[builder(default = "false")]
+    pub type_effectiveness: bool,
+
+    /// Fraction of each island's creatures that migrate to another
+    /// island every `migration_interval` events. Zero means the
+    /// islands never interact.
+    #[builder(default = "0.0")]
+    pub migration_rate: f64,
+
+    #[builder(default = "100_000")]
+    pub migration_interval: u64,
+
+    #[builder(default = "Topology::Ring")]
+    pub migration_topology: Topology,
+
+    /// Seed for the rng of a new population. Once a population
+    /// exists, its rng state is saved in each checkpoint instead.
+    #[builder(default = "None")]
+    pub seed: Option<u64>,
+
+    /// How many creatures the hall of fame keeps for each ranking
+    #[builder(default = "HallOfFame::DEFAULT_SIZE")]
+    pub hall_of_fame_size: usize,
+
+    /// How many previous checkpoints are kept next to the save file,
+    /// as `SAVEFILE.1` (the newest) to `SAVEFILE.N`
+    #[builder(default = "3")]
+    pub backups: usize,
+
+    /// How checkpoints are written. Loading works with either.
+    #[builder(default = "Encoding::Json")]
+    pub checkpoint_encoding: Encoding,
+}
+
+impl Settings {
+    /// The rng a brand new population should start with
+    pub fn initial_rng(&self) -> RngState {
+        match self.seed {
+            Some(seed) => RngState::from_seed(seed),
+            None => RngState::default(),
+        }
+    }
+}
+
+/// Settings given on the command line that win over the ones saved
+/// with a population. The others are part of what the population is,
+/// but these only change how it's saved and how its islands mix.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SettingsOverrides {
+    pub migration_rate: Option<f64>,
+    pub migration_interval: Option<u64>,
+    pub migration_topology: Option<Topology>,
+    pub backups: Option<usize>,
+    pub checkpoint_encoding: Option<Encoding>,
+}
+
+impl SettingsOverrides {
+    /// Applies the overrides to `settings`, and returns the names of
+    /// the settings that changed
+    pub fn apply(&self, settings: &mut Settings) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if let Some(rate) = self.migration_rate {
+            if rate != settings.migration_rate {
+                settings.migration_rate = rate;
+                changed.push("migration rate");
+            }
+        }
+        if let Some(interval) = self.migration_interval {
+            if interval != settings.migration_interval {
+                settings.migration_interval = interval;
+                changed.push("migration interval");
+            }
+        }
+        if let Some(topology) = self.migration_topology {
+            if topology != settings.migration_topology {
+                settings.migration_topology = topology;
+                changed.push("migration topology");
+            }
+        }
+        if let Some(backups) = self.backups {
+            if backups != settings.backups {
+                settings.backups = backups;
+                changed.push("number of backups");
+            }
+        }
+        if let Some(encoding) = self.checkpoint_encoding {
+            if encoding != settings.checkpoint_encoding {
+                settings.checkpoint_encoding = encoding;
+                changed.push("checkpoint encoding");
+            }
+        }
+        changed
+    }
+}
+
+/// Decides which islands migrants move to
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+pub enum Topology {
+    /// Migrants move to the next island over
+    Ring,
+    /// Migrants are spread evenly over all other islands
+    FullyConnected,
+    /// All of an island's migrants move to one randomly chosen island
+    Random,
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Topology, String> {
+        match s {
+            "ring" => Ok(Topology::Ring),
+            "full" => Ok(Topology::FullyConnected),
+            "random" => Ok(Topology::Random),
+            _ => Err(format!("Unknown migration topology: {}", s)),
+        }
+    }
+}
+
+/// What a thread of a threaded run needs, besides its share of the
+/// population, to carry on exactly where it stopped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadState {
+    pub island: Island,
+    /// The rng the thread's worker runs events with
+    pub rng: RngState,
+    /// How many events were left in the epoch when the run stopped,
+    /// or zero if it stopped between epochs
+    pub events_left: u64,
+}
+
+/// How a checkpoint is written inside its file, after the header
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    /// JSON compressed with xz as hard as it goes. Small, but slow to
+    /// write for a large population.
+    Json,
+    /// bincode with variable length integers, compressed with a fast
+    /// xz preset. Several times faster to write than JSON.
+    Binary,
+}
+
+impl Encoding {
+    fn name(&self) -> &'static str {
+        match *self {
+            Encoding::Json => "json",
+            Encoding::Binary => "binary",
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Encoding, String> {
+        match s {
+            "json" => Ok(Encoding::Json),
+            "binary" => Ok(Encoding::Binary),
+            _ => Err(format!("Unknown checkpoint encoding: {}", s)),
+        }
+    }
 }
 
 impl Default for Settings {
@@ -31,46 +205,292 @@ impl Default for Settings {
 pub struct Saver {
     filename: String,
     settings: Settings,
+    rules: Rules,
 }
 
 impl Saver {
     pub const COMPRESSION_LEVEL: u32 = 9;
+    /// Binary checkpoints are already compact, so they're only
+    /// compressed enough to squeeze out the repetition in the DNA
+    pub const BINARY_COMPRESSION_LEVEL: u32 = 1;
+    /// The version of the checkpoint format written by `save`. Bump
+    /// this and add a migration to `MIGRATIONS` whenever a change to
+    /// what's saved would stop older checkpoints from loading.
+    /// Migrations work on JSON, so binary checkpoints from older
+    /// versions are read with the layout they were written with (see
+    /// `binary_to_json`) and turned into JSON first. Any change to
+    /// what's saved means keeping a copy of the old layout there.
+    pub const FORMAT_VERSION: u32 = 4;
+    /// How long to wait before trying a failed save again
+    pub const RETRY_SECONDS: u64 = 5;
+    /// How many times to try saving before giving up, when there
+    /// won't be another checkpoint to try with
+    pub const FINAL_ATTEMPTS: usize = 3;
 
-    pub fn new(filename: &str) -> Saver {
+    pub fn new(filename: &str, settings: Settings, rules: Rules) -> Saver {
         Saver {
             filename: filename.to_owned(),
-            settings: Settings::default(),
+            settings,
+            rules,
         }
     }
 
-    /// Save the current file to disk
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.settings = settings;
+    }
+
+    /// The name of the `n`th newest backup of a save file
+    pub fn backup_filename(filename: &str, n: usize) -> String {
+        format!("{}.{}", filename, n)
+    }
+
+    /// Save the current file to disk. The checkpoint is written to a
+    /// temporary file which replaces the save file once it's safely on
+    /// disk, so a crash while saving never leaves a half-written save
+    /// file behind. The save file it replaces becomes the newest
+    /// backup.
     pub fn save(
         &mut self,
         creatures: &Creatures,
         stats: &GlobalStatistics,
+        hall_of_fame: &HallOfFame,
+        rng: &RngState,
+        threads: &[ThreadState],
     ) -> Result<(), Error> {
         let contents = Checkpoint {
             creatures,
             stats: stats.to_owned(),
+            hall_of_fame,
             settings: self.settings.to_owned(),
+            rng,
+            rules: &self.rules,
+            threads,
         };
-        // Create a writer
-        let compressor = XzEncoder::new(
-            File::create(&self.filename)?,
-            Saver::COMPRESSION_LEVEL,
-        );
-        serde_json::to_writer(compressor, &contents)?;
-        Ok(())
+        let encoding = self.settings.checkpoint_encoding;
+        let temp_filename = format!("{}.tmp", self.filename);
+        let mut file = File::create(&temp_filename)?;
+        writeln!(
+            file,
+            "{} {} {}",
+            HEADER_MAGIC,
+            Saver::FORMAT_VERSION,
+            encoding.name()
+        )?;
+        write_checkpoint(file, &contents, encoding)?.sync_all()?;
+        self.rotate_backups()?;
+        fs::rename(&temp_filename, &self.filename)?;
+        sync_directory(&self.filename)
+    }
+
+    /// Shifts each backup to the next oldest slot, dropping the
+    /// oldest, and links the save file to the newest. Linking rather
+    /// than renaming means there's a save file the whole time.
+    fn rotate_backups(&self) -> Result<(), Error> {
+        let backups = self.settings.backups;
+        if backups == 0 || !Path::new(&self.filename).exists() {
+            return Ok(());
+        }
+        for n in (1..backups).rev() {
+            let older = Saver::backup_filename(&self.filename, n);
+            if Path::new(&older).exists() {
+                fs::rename(
+                    &older,
+                    Saver::backup_filename(&self.filename, n + 1),
+                )?;
+            }
+        }
+        let newest = Saver::backup_filename(&self.filename, 1);
+        if Path::new(&newest).exists() {
+            fs::remove_file(&newest)?;
+        }
+        // Not every filesystem has hard links
+        fs::hard_link(&self.filename, &newest)
+            .or_else(|_| fs::copy(&self.filename, &newest).map(|_| ()))
     }
 
-    /// Load a savefile from disk
+    /// Load a savefile from disk, migrating it from whichever version
+    /// of the format it was saved with
     pub fn load(filename: &str) -> Result<OwnedCheckpoint, Error> {
-        // Create reader
-        let decompressor = XzDecoder::new(File::open(filename)?);
-        let d_checkpoint: DeserializableCheckpoint =
-            serde_json::from_reader(decompressor)?;
+        let mut reader = BufReader::new(File::open(filename)?);
+        let (version, encoding) = read_header(&mut reader)?;
+        let decompressor = XzDecoder::new(reader);
+        let current = version == Saver::FORMAT_VERSION;
+        let d_checkpoint: DeserializableCheckpoint = match encoding {
+            Encoding::Binary if current => binary_options()
+                .deserialize_from(BufReader::new(decompressor))
+                .map_err(|e| bincode_error(*e))?,
+            Encoding::Json if current => serde_json::from_reader(decompressor)?,
+            _ => {
+                let mut checkpoint = match encoding {
+                    Encoding::Json => serde_json::from_reader(decompressor)?,
+                    Encoding::Binary => binary_to_json(
+                        version,
+                        BufReader::new(decompressor),
+                    )?,
+                };
+                for migration in &MIGRATIONS[version as usize - 1..] {
+                    migration(&mut checkpoint);
+                }
+                serde_json::from_value(checkpoint)?
+            }
+        };
         Ok(d_checkpoint.into_owned_checkpoint())
     }
+
+    /// Loads a savefile if there is one. Only a file that doesn't
+    /// exist counts as no savefile, one that can't be loaded is an
+    /// error so it doesn't get overwritten by a new population.
+    pub fn load_if_exists(
+        filename: &str,
+    ) -> Result<Option<OwnedCheckpoint>, Error> {
+        match Saver::load(filename) {
+            Ok(checkpoint) => Ok(Some(checkpoint)),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// The start of the line before the compressed checkpoint that says
+/// which version of the format it's in
+const HEADER_MAGIC: &str = "EVOFIGHTERS CHECKPOINT";
+
+/// Every xz stream starts with these bytes
+const XZ_MAGIC: &[u8] = b"\xFD7zXZ\x00";
+
+/// `MIGRATIONS[i]` changes a checkpoint from version `i + 1` of the
+/// format to version `i + 2`, so loading an old checkpoint applies
+/// every migration from its version onwards
+const MIGRATIONS: [fn(&mut Value); Saver::FORMAT_VERSION as usize - 1] = [
+    // Version 1 checkpoints were written before there was a header
+    unchanged,
+    // Version 2 headers didn't say how the checkpoint was encoded,
+    // since it was always JSON
+    unchanged,
+    // Version 3 didn't save how a threaded run had split up its
+    // population, which is left empty so it's split up evenly
+    unchanged,
+];
+
+/// For versions that only changed the header, not the checkpoint
+fn unchanged(_checkpoint: &mut Value) {}
+
+/// Reads the header and returns the format version and encoding,
+/// leaving `reader` at the start of the compressed checkpoint
+fn read_header<R: BufRead>(
+    reader: &mut R,
+) -> Result<(u32, Encoding), Error> {
+    if reader.fill_buf()?.starts_with(XZ_MAGIC) {
+        return Ok((1, Encoding::Json));
+    }
+    let mut line = Vec::new();
+    reader.by_ref().take(64).read_until(b'\n', &mut line)?;
+    let line = String::from_utf8_lossy(&line);
+    let not_a_checkpoint =
+        || Error::new(ErrorKind::InvalidData, "Not an EvoFighters checkpoint");
+    if !line.starts_with(HEADER_MAGIC) {
+        return Err(not_a_checkpoint());
+    }
+    let mut words = line[HEADER_MAGIC.len()..].split_whitespace();
+    let version: u32 = words
+        .next()
+        .and_then(|version| version.parse().ok())
+        .ok_or_else(not_a_checkpoint)?;
+    let encoding = match words.next() {
+        Some(encoding) => encoding
+            .parse()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?,
+        None => Encoding::Json,
+    };
+    if version == 0 || version > Saver::FORMAT_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "The checkpoint is in version {} of the format, but this \
+                 version of EvoFighters only understands up to version {}",
+                version,
+                Saver::FORMAT_VERSION
+            ),
+        ));
+    }
+    Ok((version, encoding))
+}
+
+/// Writes a checkpoint after the header, returning the writer once
+/// everything has been flushed to it
+fn write_checkpoint<W: Write>(
+    writer: W,
+    checkpoint: &Checkpoint,
+    encoding: Encoding,
+) -> Result<W, Error> {
+    match encoding {
+        Encoding::Json => {
+            let mut compressor =
+                XzEncoder::new(writer, Saver::COMPRESSION_LEVEL);
+            serde_json::to_writer(&mut compressor, checkpoint)?;
+            compressor.finish()
+        }
+        Encoding::Binary => {
+            let compressor =
+                XzEncoder::new(writer, Saver::BINARY_COMPRESSION_LEVEL);
+            let mut buffered = BufWriter::new(compressor);
+            binary_options()
+                .serialize_into(&mut buffered, checkpoint)
+                .map_err(|e| bincode_error(*e))?;
+            buffered
+                .into_inner()
+                .map_err(|e| e.into_error())?
+                .finish()
+        }
+    }
+}
+
+/// Reads a binary checkpoint from an older version of the format as
+/// the JSON that version would have written, ready to be migrated
+fn binary_to_json<R: Read>(version: u32, reader: R) -> Result<Value, Error> {
+    let checkpoint = match version {
+        3 => binary_options().deserialize_from::<_, CheckpointV3>(reader),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Version {} of the format didn't have binary checkpoints",
+                    version
+                ),
+            ))
+        }
+    };
+    let checkpoint = checkpoint.map_err(|e| bincode_error(*e))?;
+    Ok(serde_json::to_value(checkpoint)?)
+}
+
+/// Variable length integers keep ids, counts and stats small
+fn binary_options() -> impl Options {
+    bincode::DefaultOptions::new().with_varint_encoding()
+}
+
+fn bincode_error(e: bincode::ErrorKind) -> Error {
+    match e {
+        bincode::ErrorKind::Io(e) => e,
+        e => Error::new(ErrorKind::InvalidData, e),
+    }
+}
+
+/// Makes sure a rename of `filename` is on disk, not just its contents
+#[cfg(unix)]
+fn sync_directory(filename: &str) -> Result<(), Error> {
+    let directory = match Path::new(filename).parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    File::open(directory)?.sync_all()
+}
+
+/// Directories can't be opened like files on other platforms, so this
+/// is the best that can be done there
+#[cfg(not(unix))]
+fn sync_directory(_filename: &str) -> Result<(), Error> {
+    Ok(())
 }
 
 /// This checkpoint can be written to disk without needing to take
@@ -81,7 +501,11 @@ impl Saver {
 struct Checkpoint<'a> {
     creatures: &'a Creatures,
     stats: GlobalStatistics,
+    hall_of_fame: &'a HallOfFame,
     settings: Settings,
+    rng: &'a RngState,
+    rules: &'a Rules,
+    threads: &'a [ThreadState],
 }
 
 /// This checkpoint owns its creatures array. It's public because when
@@ -89,7 +513,12 @@ struct Checkpoint<'a> {
 pub struct OwnedCheckpoint {
     pub creatures: Creatures,
     pub stats: GlobalStatistics,
+    pub hall_of_fame: HallOfFame,
     pub settings: Settings,
+    pub rng: RngState,
+    pub rules: Rules,
+    /// Empty unless the checkpoint was saved by a threaded run
+    pub threads: Vec<ThreadState>,
 }
 
 /// This checkpoint is what's deserialized from disk. Several of the
@@ -102,7 +531,28 @@ pub struct OwnedCheckpoint {
 struct DeserializableCheckpoint {
     pub creatures: DeserializableCreatures,
     pub stats: GlobalStatistics,
+    #[serde(default)]
+    pub hall_of_fame: HallOfFame,
     pub settings: Settings,
+    #[serde(default)]
+    pub rng: RngState,
+    /// Checkpoints from before rules files existed used the defaults
+    #[serde(default)]
+    pub rules: Rules,
+    #[serde(default)]
+    pub threads: Vec<ThreadState>,
+}
+
+/// A checkpoint as version 3 of the format laid it out, which was the
+/// first version with binary checkpoints
+#[derive(Deserialize, Serialize)]
+struct CheckpointV3 {
+    creatures: DeserializableCreatures,
+    stats: GlobalStatistics,
+    hall_of_fame: HallOfFame,
+    settings: Settings,
+    rng: RngState,
+    rules: Rules,
 }
 
 impl DeserializableCheckpoint {
@@ -110,12 +560,220 @@ impl DeserializableCheckpoint {
         let DeserializableCheckpoint {
             creatures: deserialized_creatures,
             stats,
+            hall_of_fame,
             settings,
+            rng,
+            rules,
+            threads,
         } = self;
         OwnedCheckpoint {
             creatures: deserialized_creatures.into_creatures(),
             stats,
+            hall_of_fame,
             settings,
+            rng,
+            rules,
+            threads,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use test::Bencher;
+
+    #[test]
+    fn saving_rotates_backups() {
+        let path = env::temp_dir().join("evofighters_backup_test.evo");
+        let filename = path.to_str().unwrap();
+        let settings = SettingsBuilder::default()
+            .max_population_size(10)
+            .backups(2)
+            .build()
+            .unwrap();
+        let rules = Rules::default();
+        let mut rng = settings.initial_rng();
+        let mut saver = Saver::new(filename, settings, rules.clone());
+        let hall_of_fame = HallOfFame::new(settings.hall_of_fame_size);
+        let mut stats = GlobalStatistics::new();
+        for events in 1..5 {
+            stats.events = events;
+            let creatures = Creatures::new(10, rng.spawn(), &rules);
+            saver
+                .save(&creatures, &stats, &hall_of_fame, &rng, &[])
+                .unwrap();
+        }
+        let events_in = |filename: &str| {
+            let events = Saver::load(filename).unwrap().stats.events;
+            fs::remove_file(filename).unwrap();
+            events
+        };
+        assert!(!Path::new(&format!("{}.tmp", filename)).exists());
+        assert!(!Path::new(&Saver::backup_filename(filename, 3)).exists());
+        assert_eq!(events_in(&Saver::backup_filename(filename, 2)), 2);
+        assert_eq!(events_in(&Saver::backup_filename(filename, 1)), 3);
+        assert_eq!(events_in(filename), 4);
+    }
+
+    #[test]
+    fn overrides_only_change_the_settings_given() {
+        let mut settings = SettingsBuilder::default()
+            .mutation_rate(0.5)
+            .backups(3)
+            .build()
+            .unwrap();
+        let overrides = SettingsOverrides {
+            backups: Some(3),
+            checkpoint_encoding: Some(Encoding::Binary),
+            ..SettingsOverrides::default()
+        };
+        assert_eq!(overrides.apply(&mut settings), ["checkpoint encoding"]);
+        assert_eq!(settings.checkpoint_encoding, Encoding::Binary);
+        assert_eq!(settings.backups, 3);
+        assert_eq!(settings.mutation_rate, 0.5);
+    }
+
+    #[test]
+    fn loads_headerless_checkpoints_and_rejects_newer_ones() {
+        let path = env::temp_dir().join("evofighters_version_test.evo");
+        let filename = path.to_str().unwrap();
+        let settings = SettingsBuilder::default().backups(0).build().unwrap();
+        let rules = Rules::default();
+        let mut rng = settings.initial_rng();
+        let creatures = Creatures::new(10, rng.spawn(), &rules);
+        Saver::new(filename, settings, rules)
+            .save(
+                &creatures,
+                &GlobalStatistics::new(),
+                &HallOfFame::new(settings.hall_of_fame_size),
+                &rng,
+                &[],
+            )
+            .unwrap();
+        let saved = fs::read(filename).unwrap();
+        let header_end = saved.iter().position(|&b| b == b'\n').unwrap() + 1;
+
+        fs::write(filename, &saved[header_end..]).unwrap();
+        assert_eq!(Saver::load(filename).unwrap().creatures.len(), 10);
+
+        let mut newer = format!("{} 999\n", HEADER_MAGIC).into_bytes();
+        newer.extend_from_slice(&saved[header_end..]);
+        fs::write(filename, newer).unwrap();
+        let err = Saver::load_if_exists(filename).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn binary_checkpoints_load_the_same_as_json_ones() {
+        let path = env::temp_dir().join("evofighters_encoding_test.evo");
+        let filename = path.to_str().unwrap();
+        let rules = Rules::default();
+        let mut rng = RngState::from_seed(3);
+        let creatures = Creatures::new(50, rng.spawn(), &rules);
+        let mut stats = GlobalStatistics::new();
+        stats.events = 1234;
+        let hall_of_fame = HallOfFame::new(HallOfFame::DEFAULT_SIZE);
+        let mut loaded = Vec::new();
+        for &encoding in &[Encoding::Json, Encoding::Binary] {
+            let settings = SettingsBuilder::default()
+                .backups(0)
+                .checkpoint_encoding(encoding)
+                .build()
+                .unwrap();
+            Saver::new(filename, settings, rules.clone())
+                .save(&creatures, &stats, &hall_of_fame, &rng, &[])
+                .unwrap();
+            let checkpoint = Saver::load(filename).unwrap();
+            assert_eq!(checkpoint.settings.checkpoint_encoding, encoding);
+            assert_eq!(checkpoint.stats.events, 1234);
+            loaded.push(serde_json::to_value(&checkpoint.creatures).unwrap());
+        }
+        fs::remove_file(filename).unwrap();
+        assert_eq!(loaded[0], loaded[1]);
+        assert_eq!(loaded[0], serde_json::to_value(&creatures).unwrap());
+    }
+
+    #[test]
+    fn migrates_binary_checkpoints_from_older_versions() {
+        // What `Checkpoint` looked like in version 3
+        #[derive(Serialize)]
+        struct Version3<'a> {
+            creatures: &'a Creatures,
+            stats: GlobalStatistics,
+            hall_of_fame: &'a HallOfFame,
+            settings: Settings,
+            rng: &'a RngState,
+            rules: &'a Rules,
+        }
+        let path = env::temp_dir().join("evofighters_old_binary_test.evo");
+        let filename = path.to_str().unwrap();
+        let rules = Rules::default();
+        let rng = RngState::from_seed(4);
+        let creatures = Creatures::new(20, rng.clone(), &rules);
+        let mut stats = GlobalStatistics::new();
+        stats.events = 4321;
+        let settings = SettingsBuilder::default()
+            .checkpoint_encoding(Encoding::Binary)
+            .build()
+            .unwrap();
+        let mut compressor = XzEncoder::new(
+            format!("{} 3 binary\n", HEADER_MAGIC).into_bytes(),
+            Saver::BINARY_COMPRESSION_LEVEL,
+        );
+        binary_options()
+            .serialize_into(
+                &mut compressor,
+                &Version3 {
+                    creatures: &creatures,
+                    stats,
+                    hall_of_fame: &HallOfFame::new(HallOfFame::DEFAULT_SIZE),
+                    settings,
+                    rng: &rng,
+                    rules: &rules,
+                },
+            )
+            .unwrap();
+        fs::write(filename, compressor.finish().unwrap()).unwrap();
+        let checkpoint = Saver::load(filename).unwrap();
+        fs::remove_file(filename).unwrap();
+        assert_eq!(checkpoint.stats.events, 4321);
+        assert_eq!(checkpoint.settings.checkpoint_encoding, Encoding::Binary);
+        assert!(checkpoint.threads.is_empty());
+        assert_eq!(
+            serde_json::to_value(&checkpoint.creatures).unwrap(),
+            serde_json::to_value(&creatures).unwrap()
+        );
+    }
+
+    fn bench_saving(b: &mut Bencher, encoding: Encoding) {
+        let rules = Rules::default();
+        let mut rng = RngState::from_seed(1);
+        let creatures = Creatures::new(10_000, rng.spawn(), &rules);
+        let hall_of_fame = HallOfFame::new(HallOfFame::DEFAULT_SIZE);
+        let checkpoint = Checkpoint {
+            creatures: &creatures,
+            stats: GlobalStatistics::new(),
+            hall_of_fame: &hall_of_fame,
+            settings: Settings::default(),
+            rng: &rng,
+            rules: &rules,
+            threads: &[],
+        };
+        let save = || write_checkpoint(Vec::new(), &checkpoint, encoding);
+        b.bytes = save().unwrap().len() as u64;
+        b.iter(save);
+    }
+
+    #[bench]
+    fn bench_saving_json(b: &mut Bencher) {
+        bench_saving(b, Encoding::Json);
+    }
+
+    #[bench]
+    fn bench_saving_binary(b: &mut Bencher) {
+        bench_saving(b, Encoding::Binary);
+    }
+}
@@ -0,0 +1,175 @@
+//! The genomes a new population starts out with. By default every
+//! creature gets `DNA::seed`, but a file can list other genomes, one
+//! per line, as raw bases or as assembly:
+//!
+//! ```text
+//! # A third of the population mates then flees, the rest attack
+//! 1: always mate ; always flee
+//! 2: 0 1 0 -1 -1
+//! ```
+//!
+//! The number before the `:` is the genome's share of the population
+//! relative to the others, and is 1 if it's left out. Lines starting
+//! with `#` are ignored, so a hall of fame exported as DNA can be used
+//! as it is.
+
+use std::fs::File;
+use std::io::Read;
+
+use assembly;
+use dna::DNA;
+use simplify::cycle_detect;
+
+struct Genome {
+    dna: DNA,
+    weight: u64,
+}
+
+pub struct SeedDNA {
+    genomes: Vec<Genome>,
+}
+
+impl Default for SeedDNA {
+    fn default() -> SeedDNA {
+        SeedDNA {
+            genomes: vec![Genome {
+                dna: DNA::seed(),
+                weight: 1,
+            }],
+        }
+    }
+}
+
+impl SeedDNA {
+    pub fn load(filename: &str) -> Result<SeedDNA, String> {
+        let mut text = String::new();
+        File::open(filename)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|err| err.to_string())?;
+        SeedDNA::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<SeedDNA, String> {
+        let mut genomes = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let genome = Genome::parse(line)
+                .map_err(|err| format!("Line {}: {}", number + 1, err))?;
+            genomes.push(genome);
+        }
+        if genomes.is_empty() {
+            return Err("There aren't any genomes".to_owned());
+        }
+        Ok(SeedDNA { genomes })
+    }
+
+    pub fn len(&self) -> usize {
+        self.genomes.len()
+    }
+
+    /// How many creatures in a population of `size` get each genome.
+    /// Shares that don't come out whole are rounded so the counts add
+    /// up to `size`.
+    pub fn counts(&self, size: usize) -> Vec<(&DNA, usize)> {
+        // Any weight fits in a u64, so none of this can overflow
+        let weight = |g: &Genome| u128::from(g.weight);
+        let total: u128 = self.genomes.iter().map(weight).sum();
+        let size = size as u128;
+        let mut counts: Vec<(&DNA, usize)> = self
+            .genomes
+            .iter()
+            .map(|g| (&g.dna, (size * weight(g) / total) as usize))
+            .collect();
+        let given: usize = counts.iter().map(|&(_, count)| count).sum();
+        let mut by_remainder: Vec<usize> = (0..counts.len()).collect();
+        by_remainder.sort_by_key(|&i| {
+            total - size * weight(&self.genomes[i]) % total
+        });
+        for &i in by_remainder.iter().take(size as usize - given) {
+            counts[i].1 += 1;
+        }
+        counts
+    }
+}
+
+impl Genome {
+    fn parse(line: &str) -> Result<Genome, String> {
+        let (weight, genome) = match line.find(':') {
+            Some(colon) => {
+                let weight = line[..colon].trim();
+                match weight.parse() {
+                    Ok(weight) if weight > 0 => (weight, &line[colon + 1..]),
+                    _ => {
+                        return Err(format!(
+                            "'{}' isn't a valid share of the population",
+                            weight
+                        ))
+                    }
+                }
+            }
+            None => (1, line),
+        };
+        let dna = assembly::read_dna(genome)?;
+        cycle_detect(&dna).map_err(|failure| {
+            format!("This DNA can't make up its mind ({:?})", failure)
+        })?;
+        Ok(Genome { dna, weight })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(seed_dna: &SeedDNA, size: usize) -> Vec<usize> {
+        seed_dna
+            .counts(size)
+            .into_iter()
+            .map(|(_, count)| count)
+            .collect()
+    }
+
+    #[test]
+    fn genomes_get_their_share_of_the_population() {
+        let seed_dna = SeedDNA::parse(
+            "# [Creature 12] from a hall of fame export\n\
+             0 2 -1 0 8\n\
+             \n\
+             2: always attack fire ; always flee\n\
+             3 : always mate # comment\n",
+        ).unwrap();
+        assert_eq!(seed_dna.len(), 3);
+        assert_eq!(counts(&seed_dna, 60), vec![10, 20, 30]);
+        assert_eq!(counts(&seed_dna, 10), vec![2, 3, 5]);
+        assert_eq!(counts(&seed_dna, 1), vec![0, 0, 1]);
+        assert_eq!(counts(&SeedDNA::default(), 7), vec![7]);
+        let huge = SeedDNA::parse(
+            "18446744073709551615: always mate\n\
+             18446744073709551615: always flee\n\
+             1: always wait\n",
+        ).unwrap();
+        assert_eq!(counts(&huge, 11), vec![6, 5, 0]);
+    }
+
+    #[test]
+    fn bad_genomes_say_which_line_they_are_on() {
+        assert_eq!(
+            SeedDNA::parse("always mate\n0: always flee").err().unwrap(),
+            "Line 2: '0' isn't a valid share of the population"
+        );
+        assert_eq!(
+            SeedDNA::parse("\nalways dance").err().unwrap(),
+            "Line 2: Expected an action but found 'dance' (word 2)"
+        );
+        assert_eq!(
+            SeedDNA::parse("0 200 8").err().unwrap(),
+            "Line 1: Base 200 is out of range, bases go from -128 to 127 \
+             (word 2)"
+        );
+        assert!(SeedDNA::parse("-1 -1 -1").is_err());
+        assert!(SeedDNA::parse("# nothing\n").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! An interactive shell for poking at a population without
+//! restarting the process, in the spirit of the old Python `EvoCmd`.
+
+use std::io;
+use std::io::{BufRead, Write};
+
+use arena::Arena;
+use creatures::{Creature, CreatureID};
+use diversity;
+use diversity::DiversityReport;
+use saver::{Saver, Settings};
+use stats::Ranking;
+
+const HELP: &str = "\
+Commands:
+  count                Number of creatures and feeders
+  stats                Global statistics for the whole run
+  list [N]             One line summaries of the first N creatures
+  fame                 The most notable creatures that have died
+  diversity [N]        Genetic diversity and the N biggest species
+  show ID              Everything about a single creature
+  settings             Show the current settings
+  set NAME VALUE       Change a setting (mutation_rate,
+                       max_population_size, metric_fps,
+                       type_effectiveness, checkpoint_encoding)
+  run N                Run N events
+  fight ID1 ID2        Make two creatures fight (0 is a feeder)
+  save                 Save a checkpoint to the save file
+  load                 Throw away unsaved changes and reload the save file
+  help                 Show this message
+  quit                 Leave the shell without saving";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Count,
+    Stats,
+    List(usize),
+    Fame,
+    Diversity(usize),
+    Show(CreatureID),
+    Settings,
+    Set(String, String),
+    Run(u64),
+    Fight(CreatureID, CreatureID),
+    Save,
+    Load,
+    Help,
+    Quit,
+}
+
+impl Command {
+    const DEFAULT_LIST_SIZE: usize = 10;
+
+    /// Parses a line of input. Blank lines parse to `None`.
+    fn parse(line: &str) -> Result<Option<Command>, String> {
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let args: Vec<&str> = words.collect();
+        let command = match (name, args.len()) {
+            ("count", 0) => Command::Count,
+            ("stats", 0) => Command::Stats,
+            ("list", 0) => Command::List(Command::DEFAULT_LIST_SIZE),
+            ("list", 1) => Command::List(parse_arg(args[0])?),
+            ("fame", 0) => Command::Fame,
+            ("diversity", 0) => {
+                Command::Diversity(DiversityReport::SHOWN_SPECIES)
+            }
+            ("diversity", 1) => Command::Diversity(parse_arg(args[0])?),
+            ("show", 1) => Command::Show(parse_arg(args[0])?),
+            ("settings", 0) => Command::Settings,
+            ("set", 2) => {
+                Command::Set(args[0].to_owned(), args[1].to_owned())
+            }
+            ("run", 1) | ("simulate", 1) => {
+                Command::Run(parse_arg(args[0])?)
+            }
+            ("fight", 2) => {
+                Command::Fight(parse_arg(args[0])?, parse_arg(args[1])?)
+            }
+            ("save", 0) => Command::Save,
+            ("load", 0) => Command::Load,
+            ("help", _) | ("?", _) => Command::Help,
+            ("quit", 0) | ("exit", 0) => Command::Quit,
+            _ => {
+                return Err(format!(
+                    "Didn't understand '{}', try 'help'",
+                    line.trim()
+                ))
+            }
+        };
+        Ok(Some(command))
+    }
+}
+
+fn parse_arg<T: ::std::str::FromStr>(arg: &str) -> Result<T, String> {
+    arg.parse()
+        .map_err(|_| format!("'{}' isn't a valid argument", arg))
+}
+
+/// Returns a copy of `settings` with the named setting changed
+fn change_setting(
+    settings: &Settings,
+    name: &str,
+    value: &str,
+) -> Result<Settings, String> {
+    let mut settings = *settings;
+    match name {
+        "mutation_rate" => settings.mutation_rate = parse_arg(value)?,
+        "max_population_size" => {
+            settings.max_population_size = parse_arg(value)?
+        }
+        "metric_fps" => settings.metric_fps = parse_arg(value)?,
+        "type_effectiveness" => {
+            settings.type_effectiveness = parse_arg(value)?
+        }
+        "checkpoint_encoding" => {
+            settings.checkpoint_encoding = parse_arg(value)?
+        }
+        _ => return Err(format!("There's no setting called '{}'", name)),
+    }
+    Ok(settings)
+}
+
+fn summary(creature: &Creature) -> String {
+    format!(
+        "{} gen: {} energy: {} kills: {} children: {} survived: {}",
+        creature,
+        creature.generation,
+        creature.energy(),
+        creature.stats.kills,
+        creature.stats.num_children,
+        creature.stats.survived,
+    )
+}
+
+pub struct Shell {
+    arena: Arena,
+    filename: String,
+}
+
+impl Shell {
+    pub fn new(arena: Arena, filename: &str) -> Shell {
+        Shell {
+            arena,
+            filename: filename.to_owned(),
+        }
+    }
+
+    /// Reads commands from stdin until it's closed or the user quits
+    pub fn run(&mut self) {
+        println!("Type 'help' for a list of commands");
+        let stdin = io::stdin();
+        loop {
+            print!("evofighters> ");
+            io::stdout().flush().expect("Couldn't write to stdout");
+            let mut line = String::new();
+            match stdin.lock().read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => (),
+            }
+            match Command::parse(&line) {
+                Ok(Some(Command::Quit)) => break,
+                Ok(Some(command)) => self.execute(command),
+                Ok(None) => (),
+                Err(msg) => println!("{}", msg),
+            }
+        }
+    }
+
+    fn execute(&mut self, command: Command) {
+        match command {
+            Command::Count => println!(
+                "{} creatures, {} feeders",
+                self.arena.population().len(),
+                self.arena.population().feeder_count()
+            ),
+            Command::Stats => println!(
+                "{:#?}\nEvents: {}",
+                self.arena.stats(),
+                self.arena.total_events()
+            ),
+            Command::List(n) => {
+                for creature in self.arena.population().iter().take(n) {
+                    println!("{}", summary(creature));
+                }
+            }
+            Command::Fame => {
+                for &ranking in &Ranking::ALL {
+                    println!("Most {}:", ranking);
+                    for champion in self.arena.hall_of_fame().champions(ranking)
+                    {
+                        println!("  {}", champion);
+                    }
+                }
+            }
+            Command::Diversity(n) => {
+                let mut report = diversity::analyze(self.arena.population());
+                report.species.truncate(n);
+                print!("{}", report);
+            }
+            Command::Show(id) => match self.arena.population().get(id) {
+                Some(creature) => print!("{}", creature.details()),
+                None => println!("No creature with id {}", id),
+            },
+            Command::Settings => println!("{:#?}", self.arena.settings()),
+            Command::Set(name, value) => {
+                match change_setting(self.arena.settings(), &name, &value) {
+                    Ok(settings) => self.arena.set_settings(settings),
+                    Err(msg) => println!("{}", msg),
+                }
+            }
+            Command::Run(n) => {
+                let events_run = self.arena.run(n);
+                println!(
+                    "Ran {} events, {} creatures left",
+                    events_run,
+                    self.arena.population().len()
+                );
+                if events_run < n {
+                    println!("There aren't enough creatures to go on");
+                }
+            }
+            Command::Fight(id1, id2) => self.fight(id1, id2),
+            Command::Save => match self.arena.save() {
+                Ok(()) => println!("Saved to {}", self.filename),
+                Err(e) => println!("Failed to save: {}", e),
+            },
+            Command::Load => match Saver::load(&self.filename) {
+                Ok(checkpoint) => {
+                    self.arena =
+                        Arena::from_checkpoint(checkpoint, &self.filename);
+                    println!(
+                        "Loaded {} creatures",
+                        self.arena.population().len()
+                    );
+                }
+                Err(e) => println!("Failed to load: {}", e),
+            },
+            Command::Help => println!("{}", HELP),
+            Command::Quit => (),
+        }
+    }
+
+    fn fight(&mut self, id1: CreatureID, id2: CreatureID) {
+        let stats = match self.arena.stage_fight(id1, id2) {
+            Ok(stats) => stats,
+            Err(msg) => return println!("{}", msg),
+        };
+        println!(
+            "The fight lasted {} rounds, {} children were born",
+            stats.rounds, stats.children_born
+        );
+        for &id in &[id1, id2] {
+            if id.is_feeder() {
+                continue;
+            }
+            match self.arena.population().get(id) {
+                Some(creature) => println!("{}", summary(creature)),
+                None => println!("[Creature {}] died", id),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_with_arguments() {
+        assert_eq!(
+            Command::parse("run 500\n"),
+            Ok(Some(Command::Run(500)))
+        );
+        assert_eq!(
+            Command::parse("  fight 3   0 "),
+            Ok(Some(Command::Fight(
+                "3".parse().unwrap(),
+                CreatureID::feeder()
+            )))
+        );
+        assert_eq!(
+            Command::parse("list"),
+            Ok(Some(Command::List(Command::DEFAULT_LIST_SIZE)))
+        );
+        assert_eq!(Command::parse("   \n"), Ok(None));
+        assert!(Command::parse("run lots").is_err());
+        assert!(Command::parse("save now").is_err());
+        assert!(Command::parse("dance").is_err());
+    }
+
+    #[test]
+    fn changes_only_the_named_setting() {
+        let settings = Settings::default();
+        let changed =
+            change_setting(&settings, "mutation_rate", "0.5").unwrap();
+        assert_eq!(changed.mutation_rate, 0.5);
+        assert_eq!(
+            changed.max_population_size,
+            settings.max_population_size
+        );
+        assert!(change_setting(&settings, "mutation_rate", "x").is_err());
+        assert!(change_setting(&settings, "nonsense", "1").is_err());
+    }
+}
@@ -1,12 +1,36 @@
+use std::cmp::min;
+use std::mem;
 use std::thread;
-use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+use std::io;
+use std::io::Write;
+use ctrlc;
 use num_cpus;
 
-use arena::Arena;
-use saver::{Saver, Settings};
-use creatures::Creatures;
+use arena;
+use arena::{Arena, Chronicle};
+use saver::{Saver, Settings, SettingsOverrides, ThreadState, Topology};
+use creatures::{Creature, Creatures, Island};
+use dashboard;
+use dashboard::{Dashboard, Status};
+use diversity;
+use diversity::DiversityReport;
 use saver::OwnedCheckpoint;
+use hall_of_fame::HallOfFame;
+use lineage::{BirthRecord, LineageLog};
+use metrics::{MetricsLog, Sample};
 use stats::GlobalStatistics;
+use rng::RngState;
+use rules::Rules;
+use seed_dna::SeedDNA;
+
+/// Who the Ctrl-C and SIGTERM handler tells to stop. The handler can
+/// only be set once per process, so each run replaces these instead.
+static EXIT_SENDERS: Mutex<Vec<Sender<()>>> = Mutex::new(Vec::new());
+/// Whether the handler was set, or why it couldn't be
+static EXIT_HANDLER: OnceLock<Result<(), String>> = OnceLock::new();
 
 /// Simulation is the coordinating object that manages all of the
 /// different threads used to run the sim. It decides how many workers
@@ -19,75 +43,1028 @@ use stats::GlobalStatistics;
 ///   * `should_exit` - a `Receiver` to get graceful shutdown messages
 ///
 /// There is one `Saver` thread
-///   * `checkpoints` - a `Receiver` of checkpoints to save to disk
-///   * `metrics` - a `Receiver` of metrics to save to disk
-///   * `should_exit` - a `Receiver` to get graceful shutdown messages
+///   * `checkpoints` - a `Receiver` of checkpoints and births to save to
+///     disk
+///   * `metrics` - a `Receiver` of metrics to save to the metrics log
 ///
 /// On the main thread:
 ///   * `worker_out` - a `Vec<Sender>` with channels to send work to `Worker`s
 ///   * `worker_in` - a `Vec<Receiver>` with channels to receive completed work
-///   * `metrics` - a `Sender` channel to send metrics through
-///   * `should_exit` - a `Sender` to send graceful shutdown messages
+///   * `checkpoints` - a `Sender` channel to send checkpoints through
+///   * `should_exit` - a `Receiver` to get graceful shutdown messages
+///
+/// Graceful shutdown messages are sent by the Ctrl-C and SIGTERM
+/// handler. Workers hand back their work early, the main thread sends
+/// a final checkpoint and hangs up, and the `Saver` exits once it's
+/// saved everything it was sent.
 pub struct Simulation {
     filename: String,
-    arena: Arena,
     settings: Settings,
+    rules: Rules,
+    num_threads: usize,
+    metrics_log: Option<MetricsLog>,
+    lineage: bool,
+    dashboard: Option<Arc<Dashboard>>,
+    seed_dna: Option<SeedDNA>,
+    overrides: SettingsOverrides,
+    /// Stops a threaded run after this many epochs, as if it had been
+    /// asked to quit
+    max_epochs: Option<u64>,
 }
 
 impl Simulation {
-    pub fn new(filename: &str, settings: Settings) -> Simulation {
-        let arena = match Saver::load(filename) {
-            Ok(checkpoint) => {
-                println!("Loading from file {}", filename);
-                Arena::from_checkpoint(checkpoint, filename)
-            }
-            Err(_) => {
-                println!("Creating initial population");
-                let population: Creatures =
-                    Creatures::new(settings.max_population_size);
-                println!("Created {} creatures", settings.max_population_size);
-                Arena::new(population, filename, settings)
-            }
-        };
+    /// How many events each worker runs before the first checkpoint,
+    /// before we have any idea how fast the workers are
+    const INITIAL_EVENTS_PER_EPOCH: u64 = 100_000;
+    /// How many seconds we aim to have between checkpoints
+    const SECONDS_PER_CHECKPOINT: f64 = 30.0;
 
+    pub fn new(
+        filename: &str,
+        settings: Settings,
+        rules: Rules,
+        num_threads: Option<usize>,
+    ) -> Simulation {
         Simulation {
             filename: filename.to_owned(),
             settings,
-            arena,
+            rules,
+            num_threads: num_threads.unwrap_or_else(num_cpus::get_physical),
+            metrics_log: None,
+            lineage: true,
+            dashboard: None,
+            seed_dna: None,
+            overrides: SettingsOverrides::default(),
+            max_epochs: None,
         }
     }
 
-    pub fn load_or_create(&mut self) -> OwnedCheckpoint {
-        println!("Attempting to load checkpoint from {}...", self.filename);
-        match Saver::load(&self.filename) {
-            Ok(checkpoint) => {
-                println!(
+    /// Starts a new population with the genomes in `seed_dna`. Has no
+    /// effect if there's already a save file to carry on from.
+    pub fn seed_with(&mut self, seed_dna: SeedDNA) {
+        self.seed_dna = Some(seed_dna);
+    }
+
+    /// Uses `overrides` instead of the settings saved with the
+    /// population, if there's already one to carry on from
+    pub fn override_saved_settings(&mut self, overrides: SettingsOverrides) {
+        self.overrides = overrides;
+    }
+
+    /// Reports the status to `dashboard`, and takes save, pause and
+    /// quit requests from it
+    pub fn attach_dashboard(&mut self, dashboard: Arc<Dashboard>) {
+        self.dashboard = Some(dashboard);
+    }
+
+    /// Appends a sample to `log` at `metric_fps` while simulating
+    pub fn log_metrics(&mut self, log: MetricsLog) {
+        self.metrics_log = Some(log);
+    }
+
+    /// Whether to add births to the lineage log, which is on unless
+    /// this turns it off
+    pub fn log_lineage(&mut self, lineage: bool) {
+        self.lineage = lineage;
+    }
+
+    fn announce(&self, message: &str) {
+        dashboard::announce(&self.dashboard, message);
+    }
+
+    /// Warns that the seeds for a new population aren't used, since
+    /// there's already a population to carry on with
+    fn ignore_seeds(&mut self) {
+        if self.settings.seed.is_some() {
+            self.announce(
+                "Not using the seed, since the population already exists \
+                 and carries on from its saved rng state.",
+            );
+        }
+        if self.seed_dna.take().is_some() {
+            self.announce(
+                "Not using the seed DNA, since the population already exists.",
+            );
+        }
+    }
+
+    /// Applies the overrides to the settings of a loaded population,
+    /// and says which ones it changed
+    fn override_settings(&self, settings: &mut Settings) {
+        for name in self.overrides.apply(settings) {
+            self.announce(&format!(
+                "Using the {} given on the command line instead of the \
+                 saved one.",
+                name
+            ));
+        }
+    }
+
+    /// Drops births from the lineage log that happened after the
+    /// population was saved `events` events into the run, so a run that
+    /// carries on from the checkpoint doesn't log them a second time
+    fn forget_later_births(&self, events: u64) {
+        match LineageLog::for_savefile(&self.filename).truncate_after(events) {
+            Ok(0) => (),
+            Ok(dropped) => self.announce(&format!(
+                "Dropped {} births from the lineage log that happened \
+                 after the checkpoint.",
+                dropped
+            )),
+            Err(e) => {
+                self.announce(&format!("Couldn't trim the lineage log: {}", e))
+            }
+        }
+    }
+
+    /// Loads the save file, or creates a new population if there
+    /// isn't one. A save file that can't be loaded is an error.
+    pub fn load_or_create(&mut self) -> io::Result<OwnedCheckpoint> {
+        self.announce(&format!(
+            "Attempting to load checkpoint from {}...",
+            self.filename
+        ));
+        Ok(match Saver::load_if_exists(&self.filename)? {
+            Some(mut checkpoint) => {
+                self.announce(&format!(
                     "Success. {} creatures loaded.",
                     checkpoint.creatures.len()
-                );
+                ));
+                self.ignore_seeds();
+                self.forget_later_births(checkpoint.stats.events);
+                self.override_settings(&mut checkpoint.settings);
                 checkpoint
             }
-            Err(_) => {
-                let creatures =
-                    Creatures::new(self.settings.max_population_size);
-                println!(
+            None => {
+                self.forget_later_births(0);
+                let mut rng = self.settings.initial_rng();
+                let creatures = Creatures::seeded(
+                    self.settings.max_population_size,
+                    rng.spawn(),
+                    &self.rules,
+                    &self.seed_dna.take().unwrap_or_default(),
+                );
+                self.announce(&format!(
                     "Created {} creatures.",
                     self.settings.max_population_size
-                );
+                ));
                 OwnedCheckpoint {
                     creatures,
                     settings: self.settings,
                     stats: GlobalStatistics::default(),
+                    hall_of_fame: HallOfFame::new(
+                        self.settings.hall_of_fame_size,
+                    ),
+                    rng,
+                    rules: self.rules.clone(),
+                    threads: Vec::new(),
                 }
             }
+        })
+    }
+
+    /// Catches Ctrl-C and SIGTERM, and sends a message to each of the
+    /// returned receivers when one of them arrives. The receivers from
+    /// an earlier call stop getting messages. If the signals can't be
+    /// caught they kill the process like they usually do.
+    fn exit_signals(&self, receivers: usize) -> Vec<Receiver<()>> {
+        let (senders, receivers): (Vec<Sender<()>>, Vec<Receiver<()>>) =
+            (0..receivers).map(|_| channel()).unzip();
+        *EXIT_SENDERS.lock().unwrap() = senders;
+        let handled = EXIT_HANDLER.get_or_init(|| {
+            ctrlc::set_handler(|| {
+                for should_exit in EXIT_SENDERS.lock().unwrap().iter() {
+                    // Whoever hung up has already stopped
+                    let _ = should_exit.send(());
+                }
+            }).map_err(|e| e.to_string())
+        });
+        if let Err(ref e) = *handled {
+            self.announce(&format!("Couldn't catch Ctrl-C: {}", e));
         }
+        receivers
     }
 
-    pub fn simulate(&mut self) {
-        self.arena.simulate()
+    /// Simulates until the population dies out or it's asked to stop.
+    /// Only fails if there's a save file that can't be loaded.
+    pub fn simulate(&mut self) -> io::Result<()> {
+        if self.num_threads > 1 {
+            self.full_simulate()
+        } else {
+            let checkpoint = self.load_or_create()?;
+            let mut arena = Arena::from_checkpoint(checkpoint, &self.filename);
+            if let Some(should_exit) = self.exit_signals(1).pop() {
+                arena.exit_on(should_exit);
+            }
+            if let Some(log) = self.metrics_log.take() {
+                arena.log_metrics(log);
+            }
+            arena.log_lineage(self.lineage);
+            if let Some(dashboard) = self.dashboard.take() {
+                arena.attach_dashboard(dashboard);
+            }
+            arena.simulate();
+            Ok(())
+        }
     }
 
-    pub fn full_simulate(&mut self) {
-        let physical_cores = num_cpus::get_physical();
+    /// Runs the simulation with one `Worker` per thread. Each worker
+    /// gets its own slice of the population, and every epoch the
+    /// slices are sent back to the main thread so a checkpoint can be
+    /// handed off to the `Saver` thread.
+    ///
+    /// If the migration rate is above zero, each slice is treated as
+    /// an island: epochs last `migration_interval` events, and between
+    /// epochs some creatures migrate between islands.
+    ///
+    /// Seeded runs also have epochs of `migration_interval` events, and
+    /// each thread's rng and share of the population is saved with the
+    /// checkpoint, so a resumed run does exactly what it would have
+    /// done if it hadn't stopped.
+    pub fn full_simulate(&mut self) -> io::Result<()> {
+        let num_threads = self.num_threads;
+        self.announce(&format!(
+            "Attempting to load checkpoint from {}...",
+            self.filename
+        ));
+        let (
+            (mut populations, mut worker_rngs, mut events_left),
+            mut stats,
+            mut hall_of_fame,
+            mut rng,
+        ) = match Saver::load_if_exists(&self.filename)? {
+            Some(checkpoint) => {
+                self.announce(&format!(
+                    "Success. {} creatures loaded.",
+                    checkpoint.creatures.len()
+                ));
+                self.ignore_seeds();
+                self.forget_later_births(checkpoint.stats.events);
+                let mut settings = checkpoint.settings;
+                self.override_settings(&mut settings);
+                self.settings = settings;
+                self.rules = checkpoint.rules;
+                let mut rng = checkpoint.rng;
+                (
+                    self.resume_threads(
+                        checkpoint.creatures,
+                        checkpoint.threads,
+                        &mut rng,
+                    ),
+                    checkpoint.stats,
+                    checkpoint.hall_of_fame,
+                    rng,
+                )
+            }
+            None => {
+                self.forget_later_births(0);
+                let mut rng = self.settings.initial_rng();
+                let populations = Creatures::per_thread(
+                    num_threads,
+                    self.settings.max_population_size,
+                    &mut rng,
+                    &self.rules,
+                    &self.seed_dna.take().unwrap_or_default(),
+                );
+                self.announce(&format!(
+                    "Created {} creatures.",
+                    self.settings.max_population_size
+                ));
+                let worker_rngs =
+                    (0..num_threads).map(|_| rng.spawn()).collect();
+                (
+                    (populations, worker_rngs, vec![0; num_threads]),
+                    GlobalStatistics::default(),
+                    HallOfFame::new(self.settings.hall_of_fame_size),
+                    rng,
+                )
+            }
+        };
+        self.announce(&format!("Running on {} threads", num_threads));
+
+        let (checkpoints_tx, checkpoints_rx) = channel();
+        let (metrics_tx, metrics_rx) = channel();
+        let mut saver_thread = SaverThread::new(
+            Saver::new(&self.filename, self.settings, self.rules.clone()),
+            LineageLog::for_savefile(&self.filename),
+            checkpoints_rx,
+            metrics_rx,
+            num_threads,
+            stats,
+            self.settings.metric_fps,
+        );
+        saver_thread.metrics_log = self.metrics_log.take();
+        saver_thread.dashboard = self.dashboard.clone();
+        let saver_handle = thread::spawn(move || saver_thread.run());
+
+        let mut should_exit = self.exit_signals(num_threads + 1);
+        let mut worker_out = Vec::with_capacity(num_threads);
+        let mut worker_in = Vec::with_capacity(num_threads);
+        let mut worker_handles = Vec::with_capacity(num_threads);
+        for (id, worker_should_exit) in should_exit.drain(1..).enumerate() {
+            let (inbox_tx, inbox_rx) = channel();
+            let (outbox_tx, outbox_rx) = channel();
+            let worker = Worker {
+                id,
+                settings: self.settings,
+                rules: self.rules.clone(),
+                lineage: self.lineage,
+                dashboard: self.dashboard.clone(),
+                inbox: inbox_rx,
+                outbox: outbox_tx,
+                metrics: metrics_tx.clone(),
+                should_exit: worker_should_exit,
+            };
+            worker_handles.push(thread::spawn(move || worker.run()));
+            worker_out.push(inbox_tx);
+            worker_in.push(outbox_rx);
+        }
+        // Only the workers should be holding on to metrics senders
+        drop(metrics_tx);
+        let should_exit = should_exit.remove(0);
+
+        let islands = self.settings.migration_rate > 0.0;
+        // Epochs that always last the same number of events line up
+        // the same way when a seeded run is resumed
+        let fixed_epochs = islands || self.settings.seed.is_some();
+        let mut events_per_epoch = if fixed_epochs {
+            self.settings.migration_interval
+        } else {
+            Simulation::INITIAL_EVENTS_PER_EPOCH
+        };
+        let mut last_checkpoint = Instant::now();
+        let mut births = Vec::new();
+        let mut epochs = 0;
+        loop {
+            // Workers only look at the dashboard every so many events,
+            // which short epochs never reach
+            if let Some(ref dashboard) = self.dashboard {
+                if dashboard.is_paused() {
+                    self.announce("\nPaused from the dashboard");
+                    dashboard.wait_while_paused();
+                    self.announce("Resumed");
+                }
+            }
+            let epoch_start = Instant::now();
+            // If the run was stopped partway through an epoch, the rest
+            // of that epoch comes first
+            let resuming_epoch = events_left.iter().any(|&left| left > 0);
+            let epoch = if resuming_epoch {
+                events_left.clone()
+            } else {
+                vec![events_per_epoch; num_threads]
+            };
+            let work = populations.drain(..).zip(worker_rngs.drain(..));
+            for ((inbox, (population, rng)), &events) in
+                worker_out.iter().zip(work).zip(&epoch)
+            {
+                inbox
+                    .send(Work {
+                        population,
+                        rng,
+                        events,
+                        first_event: stats.events,
+                    })
+                    .expect("Worker thread died");
+            }
+            let mut epoch_events = 0;
+            for ((outbox, &events), left) in
+                worker_in.iter().zip(&epoch).zip(&mut events_left)
+            {
+                let mut done = outbox.recv().expect("Worker thread died");
+                stats.absorb(done.stats);
+                hall_of_fame.absorb(done.chronicle.hall_of_fame);
+                births.append(&mut done.chronicle.births);
+                epoch_events += done.events;
+                // A worker only stops short with creatures left if it
+                // was asked to quit
+                *left = if done.population.len() >= 2 {
+                    events - done.events
+                } else {
+                    0
+                };
+                populations.push(done.population);
+                worker_rngs.push(done.rng);
+            }
+            let interrupted = events_left.iter().any(|&left| left > 0);
+            if islands && !interrupted {
+                migrate(&mut populations, &self.settings, &mut rng);
+            }
+            epochs += 1;
+            // No population slice had enough creatures left for an
+            // encounter
+            let finished = epoch_events == 0;
+            let save_requested = self.dashboard
+                .as_ref()
+                .is_some_and(|dashboard| dashboard.take_save_request());
+            let caught_signal = should_exit.try_recv().is_ok();
+            if caught_signal {
+                self.announce("\nCaught a signal, saving before exiting");
+            }
+            let quitting = caught_signal
+                || self.max_epochs.is_some_and(|max| epochs >= max)
+                || self.dashboard
+                    .as_ref()
+                    .is_some_and(|dashboard| dashboard.quit_requested());
+            if finished || !fixed_epochs || save_requested || quitting
+                || seconds(last_checkpoint.elapsed())
+                    >= Simulation::SECONDS_PER_CHECKPOINT
+            {
+                let threads = Creatures::islands(&populations)
+                    .into_iter()
+                    .zip(&worker_rngs)
+                    .zip(&events_left)
+                    .map(|((island, rng), &events_left)| ThreadState {
+                        island,
+                        rng: rng.clone(),
+                        events_left,
+                    })
+                    .collect();
+                let checkpoint = OwnedCheckpoint {
+                    creatures: Creatures::merge(populations.clone()),
+                    stats,
+                    hall_of_fame: hall_of_fame.clone(),
+                    settings: self.settings,
+                    rng: rng.clone(),
+                    rules: self.rules.clone(),
+                    threads,
+                };
+                checkpoints_tx
+                    .send((checkpoint, mem::take(&mut births)))
+                    .expect("Saver thread died");
+                last_checkpoint = Instant::now();
+            }
+            if finished || quitting {
+                break;
+            }
+            if !fixed_epochs && !resuming_epoch {
+                events_per_epoch = Simulation::next_epoch_size(
+                    events_per_epoch,
+                    epoch_start.elapsed(),
+                );
+            }
+        }
+
+        // Hanging up the channels tells the other threads to finish
+        drop(worker_out);
+        for handle in worker_handles {
+            handle.join().expect("Worker thread panicked");
+        }
+        drop(checkpoints_tx);
+        saver_handle.join().expect("Saver thread panicked");
+
+        if populations.iter().all(|population| population.len() < 2) {
+            self.announce(
+                "\nYou need at least two creatures in a population to \
+                 have an encounter. Unfortunately, this means the end for \
+                 your population.",
+            );
+        } else {
+            self.announce("\nStopped");
+        }
+        Ok(())
+    }
+
+    /// Splits a loaded population between the threads. If it was
+    /// saved by a run with as many threads, each thread carries on
+    /// exactly where it was, otherwise the population is split evenly
+    /// and each worker gets a new rng.
+    fn resume_threads(
+        &self,
+        creatures: Creatures,
+        threads: Vec<ThreadState>,
+        rng: &mut RngState,
+    ) -> (Vec<Creatures>, Vec<RngState>, Vec<u64>) {
+        let num_threads = self.num_threads;
+        if threads.len() == num_threads {
+            let islands: Vec<Island> =
+                threads.iter().map(|thread| thread.island.clone()).collect();
+            return (
+                creatures.split_into_islands(&islands),
+                threads.iter().map(|thread| thread.rng.clone()).collect(),
+                threads.iter().map(|thread| thread.events_left).collect(),
+            );
+        }
+        if !threads.is_empty() {
+            self.announce(&format!(
+                "The population was saved running on {} threads, so it \
+                 won't carry on exactly the way it would have.",
+                threads.len()
+            ));
+        }
+        (
+            creatures.split_by_thread(num_threads),
+            (0..num_threads).map(|_| rng.spawn()).collect(),
+            vec![0; num_threads],
+        )
+    }
+
+    /// Scales the number of events per epoch so the next epoch takes
+    /// about `SECONDS_PER_CHECKPOINT`
+    fn next_epoch_size(events: u64, elapsed: Duration) -> u64 {
+        let secs = seconds(elapsed);
+        if secs > 0.0 {
+            ((events as f64) * Simulation::SECONDS_PER_CHECKPOINT / secs)
+                .max(1.0) as u64
+        } else {
+            events
+        }
+    }
+}
+
+/// Takes a standard duration and returns an f64 representing seconds
+fn seconds(dur: Duration) -> f64 {
+    dur.as_secs() as f64 + f64::from(dur.subsec_nanos()) / 1_000_000_000.0
+}
+
+/// Moves `migration_rate` of each island's creatures to other islands,
+/// according to the `migration_topology`
+fn migrate(
+    populations: &mut [Creatures],
+    settings: &Settings,
+    rng: &mut RngState,
+) {
+    let num_islands = populations.len();
+    if num_islands < 2 {
+        return;
+    }
+    let mut arrivals: Vec<Vec<Creature>> = vec![Vec::new(); num_islands];
+    for (from, population) in populations.iter_mut().enumerate() {
+        let num_migrants = (population.len() as f64 * settings.migration_rate)
+            .round() as usize;
+        let num_migrants = min(num_migrants, population.len());
+        let destination = match settings.migration_topology {
+            Topology::Random => {
+                (from + rng.rand_range(1, num_islands)) % num_islands
+            }
+            _ => (from + 1) % num_islands,
+        };
+        for i in 0..num_migrants {
+            let to = match settings.migration_topology {
+                // Skips over the island the migrant is leaving
+                Topology::FullyConnected => {
+                    (from + 1 + i % (num_islands - 1)) % num_islands
+                }
+                _ => destination,
+            };
+            arrivals[to].push(population.random_creature());
+        }
+    }
+    for (population, migrants) in populations.iter_mut().zip(arrivals) {
+        population.absorb_all(migrants);
+    }
+}
+
+/// A slice of the population for a `Worker` to run events on
+struct Work {
+    population: Creatures,
+    /// The rng to run events with, which is handed back when the work
+    /// is done so it can be saved
+    rng: RngState,
+    events: u64,
+    /// The number to give the first event of this piece of work
+    first_event: u64,
+}
+
+/// A population slice sent back from a `Worker` once it's done
+struct WorkDone {
+    population: Creatures,
+    rng: RngState,
+    stats: GlobalStatistics,
+    /// Champions that died and creatures born during this piece of
+    /// work
+    chronicle: Chronicle,
+    events: u64,
+}
+
+/// Sent periodically from each `Worker` to the `Saver` thread. The
+/// stats are only what happened since the last `Metrics` were sent.
+struct Metrics {
+    worker_id: usize,
+    population: usize,
+    feeders: usize,
+    events: u64,
+    stats: GlobalStatistics,
+}
+
+struct Worker {
+    id: usize,
+    settings: Settings,
+    rules: Rules,
+    /// Whether births are kept for the lineage log
+    lineage: bool,
+    /// Workers stop between batches of metrics while it's paused, and
+    /// hand their work back early if it asks them to quit
+    dashboard: Option<Arc<Dashboard>>,
+    inbox: Receiver<Work>,
+    outbox: Sender<WorkDone>,
+    metrics: Sender<Metrics>,
+    /// Work is handed back early when a message arrives here
+    should_exit: Receiver<()>,
+}
+
+impl Worker {
+    const EVENTS_PER_METRIC: u64 = 1_000;
+
+    /// Works until the main thread hangs up
+    fn run(mut self) {
+        while let Ok(work) = self.inbox.recv() {
+            let done = self.work(work);
+            if self.outbox.send(done).is_err() {
+                break;
+            }
+        }
+    }
+
+    fn work(&mut self, work: Work) -> WorkDone {
+        let Work {
+            mut population,
+            mut rng,
+            events,
+            first_event,
+        } = work;
+        let mut stats = GlobalStatistics::new();
+        let mut chronicle = Chronicle::new(self.settings.hall_of_fame_size);
+        chronicle.worker = self.id as u16;
+        chronicle.keep_births = self.lineage;
+        chronicle.event = first_event;
+        let mut metric_stats = GlobalStatistics::new();
+        let mut metric_events = 0;
+        let mut events_done = 0;
+        while events_done < events && population.len() >= 2 {
+            let enc_stats = arena::run_event(
+                &mut population,
+                &mut chronicle,
+                &mut rng,
+                &self.settings,
+                &self.rules,
+            );
+            stats.absorb(enc_stats);
+            metric_stats.absorb(enc_stats);
+            events_done += 1;
+            metric_events += 1;
+            if metric_events == Worker::EVENTS_PER_METRIC {
+                self.send_metrics(&population, metric_events, metric_stats);
+                metric_stats = GlobalStatistics::new();
+                metric_events = 0;
+                if let Some(ref dashboard) = self.dashboard {
+                    dashboard.wait_while_paused();
+                    if dashboard.quit_requested() {
+                        break;
+                    }
+                }
+                if self.should_exit.try_recv().is_ok() {
+                    break;
+                }
+            }
+        }
+        self.send_metrics(&population, metric_events, metric_stats);
+        WorkDone {
+            population,
+            rng,
+            stats,
+            chronicle,
+            events: events_done,
+        }
+    }
+
+    fn send_metrics(
+        &self,
+        population: &Creatures,
+        events: u64,
+        stats: GlobalStatistics,
+    ) {
+        // If the saver has gone away, there's nobody to report to
+        let _ = self.metrics.send(Metrics {
+            worker_id: self.id,
+            population: population.len(),
+            feeders: population.feeder_count(),
+            events,
+            stats,
+        });
+    }
+}
+
+/// Saves checkpoints handed to it by the main thread, along with the
+/// births since the last checkpoint, and reports the combined metrics
+/// from all `Worker`s
+struct SaverThread {
+    saver: Saver,
+    lineage: LineageLog,
+    checkpoints: Receiver<(OwnedCheckpoint, Vec<BirthRecord>)>,
+    metrics: Receiver<Metrics>,
+    stats: GlobalStatistics,
+    /// Statistics since the last checkpoint
+    window_stats: GlobalStatistics,
+    populations: Vec<usize>,
+    feeders: Vec<usize>,
+    total_events: u64,
+    metric_fps: f64,
+    metrics_log: Option<MetricsLog>,
+    dashboard: Option<Arc<Dashboard>>,
+    /// Births that haven't made it into the lineage log yet because
+    /// saving failed
+    unsaved_births: Vec<BirthRecord>,
+    /// A checkpoint that failed to save, and when to try it again
+    retry: Option<(OwnedCheckpoint, Instant)>,
+}
+
+impl SaverThread {
+    fn new(
+        saver: Saver,
+        lineage: LineageLog,
+        checkpoints: Receiver<(OwnedCheckpoint, Vec<BirthRecord>)>,
+        metrics: Receiver<Metrics>,
+        num_threads: usize,
+        stats: GlobalStatistics,
+        metric_fps: f64,
+    ) -> SaverThread {
+        SaverThread {
+            saver,
+            lineage,
+            checkpoints,
+            metrics,
+            stats,
+            window_stats: GlobalStatistics::new(),
+            populations: vec![0; num_threads],
+            feeders: vec![0; num_threads],
+            total_events: 0,
+            metric_fps,
+            metrics_log: None,
+            dashboard: None,
+            unsaved_births: Vec::new(),
+            retry: None,
+        }
+    }
+
+    /// Runs until all workers have hung up their metrics channels and
+    /// the main thread has hung up the checkpoints channel
+    fn run(mut self) {
+        let frame = Duration::from_millis((1000.0 / self.metric_fps) as u64);
+        let mut last_print = Instant::now();
+        let mut events_since_last_print = 0;
+        loop {
+            match self.metrics.recv_timeout(frame) {
+                Ok(metrics) => {
+                    events_since_last_print += metrics.events;
+                    self.absorb(metrics);
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if last_print.elapsed() >= frame {
+                let eps = (events_since_last_print as f64
+                    / seconds(last_print.elapsed()))
+                    as u64;
+                if !dashboard::terminal_taken(&self.dashboard) {
+                    self.print_status(eps);
+                }
+                self.record_metrics(eps);
+                self.update_dashboard(eps, seconds(last_print.elapsed()));
+                events_since_last_print = 0;
+                last_print = Instant::now();
+            }
+            while let Ok((checkpoint, births)) = self.checkpoints.try_recv() {
+                self.save(checkpoint, births);
+            }
+            self.maybe_retry();
+        }
+        while let Ok((checkpoint, births)) = self.checkpoints.recv() {
+            self.save(checkpoint, births);
+        }
+        self.final_retries();
+    }
+
+    fn absorb(&mut self, metrics: Metrics) {
+        self.stats.absorb(metrics.stats);
+        self.window_stats.absorb(metrics.stats);
+        self.total_events += metrics.events;
+        self.populations[metrics.worker_id] = metrics.population;
+        self.feeders[metrics.worker_id] = metrics.feeders;
+    }
+
+    fn print_status(&self, eps: u64) {
+        let creatures: usize = self.populations.iter().sum();
+        let feeders: usize = self.feeders.iter().sum();
+        print!(
+            "\rCreatures: {creatures}, \
+             Feeders: {feeders}, \
+             F/C: {feeder_creature:.3}, \
+             Mutations: {mutations}, Events: {events}, \
+             Born: {born}, Eaten: {eaten}, kills: {kills}, \
+             eps: {eps}       ",
+            creatures = creatures,
+            feeders = feeders,
+            feeder_creature = feeders as f64 / creatures as f64,
+            mutations = self.stats.mutations,
+            events = self.total_events,
+            born = self.stats.children_born,
+            eaten = self.stats.feeders_eaten,
+            kills = self.stats.kills,
+            eps = eps,
+        );
+        io::stdout().flush().expect("Couldn't write to stdout");
+    }
+
+    fn update_dashboard(&self, eps: u64, frame_seconds: f64) {
+        if let Some(ref dashboard) = self.dashboard {
+            dashboard.update(Status {
+                population: self.populations.iter().sum(),
+                feeders: self.feeders.iter().sum(),
+                events_per_second: eps,
+                prediction_error: None,
+                fps: frame_seconds.recip(),
+                paused: false,
+                stats: self.stats,
+            });
+        }
+    }
+
+    fn record_metrics(&mut self, eps: u64) {
+        if let Some(ref mut log) = self.metrics_log {
+            let sample = Sample::new(
+                self.populations.iter().sum(),
+                self.feeders.iter().sum(),
+                &self.stats,
+                eps,
+            );
+            if let Err(e) = log.record(&sample) {
+                let message = format!("\nFailed to write metrics: {}", e);
+                self.announce(&message);
+            }
+        }
+    }
+
+    /// Saves a checkpoint and the births since the one before it. If
+    /// that fails, it's tried again later unless a newer checkpoint
+    /// arrives first.
+    fn save(&mut self, checkpoint: OwnedCheckpoint, births: Vec<BirthRecord>) {
+        self.unsaved_births.extend(births);
+        self.retry = None;
+        let result = self.saver
+            .save(
+                &checkpoint.creatures,
+                &checkpoint.stats,
+                &checkpoint.hall_of_fame,
+                &checkpoint.rng,
+                &checkpoint.threads,
+            )
+            .and_then(|()| self.lineage.append(&self.unsaved_births));
+        if let Err(e) = result {
+            self.announce(&format!(
+                "\nFailed to save checkpoint: {}, trying again in {} seconds",
+                e,
+                Saver::RETRY_SECONDS
+            ));
+            let retry_at =
+                Instant::now() + Duration::from_secs(Saver::RETRY_SECONDS);
+            self.retry = Some((checkpoint, retry_at));
+            return;
+        }
+        self.unsaved_births.clear();
+        self.announce("\nSaved to file");
+        self.announce(&self.window_stats.behaviour());
+        let mut report = diversity::analyze(&checkpoint.creatures);
+        report.species.truncate(DiversityReport::SHOWN_SPECIES);
+        self.announce(report.to_string().trim_end());
+        self.window_stats = GlobalStatistics::new();
+        if let Some(ref dashboard) = self.dashboard {
+            if dashboard.owns_terminal() {
+                dashboard.update_leaders(&checkpoint.creatures);
+            }
+        }
+    }
+
+    fn maybe_retry(&mut self) {
+        let due = match self.retry {
+            Some((_, retry_at)) => Instant::now() >= retry_at,
+            None => false,
+        };
+        if due {
+            let (checkpoint, _) = self.retry.take().unwrap();
+            self.save(checkpoint, Vec::new());
+        }
+    }
+
+    /// Keeps trying to save the last checkpoint for a while, since
+    /// there won't be another one
+    fn final_retries(&mut self) {
+        for _ in 1..Saver::FINAL_ATTEMPTS {
+            let checkpoint = match self.retry.take() {
+                Some((checkpoint, _)) => checkpoint,
+                None => return,
+            };
+            thread::sleep(Duration::from_secs(Saver::RETRY_SECONDS));
+            self.save(checkpoint, Vec::new());
+        }
+        if self.retry.is_some() {
+            self.announce("Giving up, the last checkpoint is all that's left");
+        }
+    }
+
+    fn announce(&self, message: &str) {
+        dashboard::announce(&self.dashboard, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use serde_json;
+    use serde_json::Value;
+    use saver::SettingsBuilder;
+
+    fn island_sizes(topology: Topology) -> Vec<usize> {
+        let settings = SettingsBuilder::default()
+            .migration_rate(0.5)
+            .migration_topology(topology)
+            .build()
+            .unwrap();
+        let mut populations = Creatures::per_thread(
+            3,
+            31,
+            &mut RngState::default(),
+            &Rules::default(),
+            &SeedDNA::default(),
+        );
+        migrate(&mut populations, &settings, &mut RngState::default());
+        populations.iter().map(|pop| pop.len()).collect()
+    }
+
+    #[test]
+    fn ring_migration_moves_to_next_island() {
+        // 11 -> 6 migrate out, 10 -> 5 migrate out
+        assert_eq!(island_sizes(Topology::Ring), vec![10, 11, 10]);
+    }
+
+    #[test]
+    fn fully_connected_migration_spreads_migrants() {
+        assert_eq!(
+            island_sizes(Topology::FullyConnected),
+            vec![10, 10, 11]
+        );
+    }
+
+    /// Runs a seeded, threaded simulation for `epochs` epochs, carrying
+    /// on from `filename` if it exists
+    #[test]
+    fn exit_signals_can_be_caught_by_more_than_one_run() {
+        let mut simulation = Simulation::new(
+            "unused.evo",
+            Settings::default(),
+            Rules::default(),
+            Some(1),
+        );
+        // Announcements only go to the dashboard while it has the
+        // terminal
+        let dashboard = Arc::new(Dashboard::new());
+        dashboard.set_owns_terminal(true);
+        simulation.attach_dashboard(Arc::clone(&dashboard));
+        assert_eq!(simulation.exit_signals(1).len(), 1);
+        assert_eq!(simulation.exit_signals(3).len(), 3);
+        assert!(dashboard.messages(10).is_empty());
+    }
+
+    fn run_epochs(filename: &str, migration_rate: f64, epochs: u64) {
+        let settings = SettingsBuilder::default()
+            .seed(Some(5))
+            .max_population_size(90)
+            .migration_rate(migration_rate)
+            .migration_interval(300)
+            .backups(0)
+            .build()
+            .unwrap();
+        let mut simulation =
+            Simulation::new(filename, settings, Rules::default(), Some(3));
+        simulation.max_epochs = Some(epochs);
+        simulation.full_simulate().unwrap();
+    }
+
+    fn saved_state(filename: &str) -> Value {
+        let checkpoint = Saver::load(filename).unwrap();
+        fs::remove_file(filename).unwrap();
+        let _ = fs::remove_file(LineageLog::for_savefile(filename).filename());
+        serde_json::to_value((
+            &checkpoint.creatures,
+            &checkpoint.stats,
+            &checkpoint.hall_of_fame,
+            &checkpoint.rng,
+            &checkpoint.threads,
+        )).unwrap()
+    }
+
+    #[test]
+    fn resuming_a_threaded_run_matches_an_uninterrupted_one() {
+        let path = env::temp_dir().join("evofighters_threaded_resume.evo");
+        let filename = path.to_str().unwrap();
+        for &migration_rate in &[0.0, 0.2] {
+            run_epochs(filename, migration_rate, 4);
+            let uninterrupted = saved_state(filename);
+            run_epochs(filename, migration_rate, 2);
+            run_epochs(filename, migration_rate, 2);
+            assert_eq!(saved_state(filename), uninterrupted);
+        }
     }
 }
@@ -1,11 +1,17 @@
 // For simplifying thought trees
 
 use std::cmp::{max, min, PartialEq, PartialOrd};
-use std::rc::Rc;
+use std::collections::BTreeSet;
+use std::sync::Arc;
 
-use dna::{ast, DNA};
+use num::FromPrimitive;
+use serde::{Serialize, Serializer};
+
+use dna::{ast, lex, Gene, DNA};
+use eval::PerformableAction;
 use parsing;
 use parsing::Decision;
+use rng::RngState;
 
 /// Simplifies a condition tree by replacing it with another condition
 /// tree that takes less (or at least not more) time to execute at
@@ -25,17 +31,13 @@ pub fn simplify(
         offset,
         icount,
         skipped,
+        ..
     }: Decision,
 ) -> Decision {
     let stage_1_cond = eval_static_conditionals(tree);
     // Next, evaluate redundant Always -> Subcondition branches
     let stage_2_cond = eval_redundant_conditions(stage_1_cond);
-    Decision {
-        tree: stage_2_cond,
-        offset,
-        icount,
-        skipped,
-    }
+    Decision::new(stage_2_cond, offset, icount, skipped)
 }
 
 /// Evaluates static conditionals at compile time.
@@ -218,16 +220,44 @@ fn erc_action(act: ast::Action) -> ast::Action {
 
 #[derive(Debug, Clone)]
 pub struct ThoughtCycle {
-    thoughts: Vec<Rc<parsing::Decision>>,
+    thoughts: Vec<Arc<parsing::Decision>>,
     cycle_offset: usize,
 }
 
 impl ThoughtCycle {
-    pub fn next(&mut self) -> Rc<Decision> {
+    pub fn next(&mut self) -> Arc<Decision> {
         let t = self.thoughts[self.cycle_offset].clone();
         self.cycle_offset = (self.cycle_offset + 1) % self.thoughts.len();
         t
     }
+
+    pub fn thoughts(&self) -> &[Arc<Decision>] {
+        &self.thoughts
+    }
+
+    /// The position in the cycle of the next thought
+    pub fn cycle_offset(&self) -> usize {
+        self.cycle_offset
+    }
+
+    /// Moves to a position in the cycle, e.g. one that was saved in a
+    /// checkpoint
+    pub fn resume_at(&mut self, cycle_offset: usize) {
+        self.cycle_offset = cycle_offset % self.thoughts.len();
+    }
+}
+
+/// Only the current position in the cycle is serialized. The thoughts
+/// themselves are recomputed from the `DNA` when loading. Old
+/// checkpoints didn't have the position, so it's an `Option` when
+/// deserialized.
+impl Serialize for ThoughtCycle {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_some(&(self.cycle_offset as u64))
+    }
 }
 
 pub fn cycle_detect(dna: &DNA) -> Result<ThoughtCycle, parsing::Failure> {
@@ -263,10 +293,760 @@ pub fn cycle_detect(dna: &DNA) -> Result<ThoughtCycle, parsing::Failure> {
     let mut thoughts = Vec::new();
     for _ in 0..(mu + lam) {
         thought = new_iter.next().unwrap().into_result()?;
-        thoughts.push(Rc::new(simplify(thought)));
+        thoughts.push(Arc::new(simplify(thought)));
     }
     Ok(ThoughtCycle {
         thoughts: thoughts,
         cycle_offset: mu,
     })
 }
+
+/// How many attributes a creature has that decisions can look at
+const ATTRIBUTES: usize = lex::Attribute::TopItem as usize + 1;
+
+/// Everything a decision can depend on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Situation {
+    /// My attributes, indexed by `lex::Attribute`
+    pub me: [usize; ATTRIBUTES],
+    /// My target's attributes, indexed by `lex::Attribute`
+    pub other: [usize; ATTRIBUTES],
+    pub random: usize,
+    pub my_last_action: PerformableAction,
+    pub other_last_action: PerformableAction,
+}
+
+impl Default for Situation {
+    fn default() -> Situation {
+        Situation {
+            me: [0; ATTRIBUTES],
+            other: [0; ATTRIBUTES],
+            random: 0,
+            my_last_action: PerformableAction::NoAction,
+            other_last_action: PerformableAction::NoAction,
+        }
+    }
+}
+
+impl Situation {
+    /// Situations picked at random, but the same ones every time,
+    /// with every input somewhere it can really be
+    pub fn probes(count: usize) -> Vec<Situation> {
+        use eval::PerformableAction::{Attack, Mate, NoAction, Wait};
+        let last_actions =
+            [NoAction, Attack(lex::DamageType::Fire), Wait, Mate];
+        let mut rng = RngState::from_seed(0);
+        let mut situations = Vec::with_capacity(count);
+        for i in 0..count {
+            let random = random_values();
+            let mut situation = Situation {
+                random: rng.rand_range(random.start, random.end),
+                my_last_action: last_actions[i % last_actions.len()],
+                other_last_action: last_actions[i / 2 % last_actions.len()],
+                ..Situation::default()
+            };
+            for attr in 0..ATTRIBUTES {
+                let values = lex::Attribute::from_usize(attr)
+                    .and_then(finite_values)
+                    // Enough to fall either side of most literals
+                    .unwrap_or(0..2 * Gene::MAX_MEANINGFUL_VALUE as usize);
+                situation.me[attr] = rng.rand_range(values.start, values.end);
+                situation.other[attr] =
+                    rng.rand_range(values.start, values.end);
+            }
+            situations.push(situation);
+        }
+        situations
+    }
+
+    /// What `tree` decides to do in this situation
+    pub fn decide(&self, tree: &ast::Condition) -> PerformableAction {
+        self.decide_knowing(tree, !0)
+            .expect("Everything about the situation is known")
+    }
+
+    /// What `tree` decides to do in this situation, which is what
+    /// `eval::evaluate` decides for creatures in it, as long as it only
+    /// looks at the inputs in the `known` set. Otherwise it's the first
+    /// one it looks at that isn't.
+    fn decide_knowing(
+        &self,
+        tree: &ast::Condition,
+        known: u32,
+    ) -> Result<PerformableAction, Input> {
+        let (holds, affirmed, denied) = match *tree {
+            ast::Condition::Always(ref action) => {
+                return self.decide_action(action, known)
+            }
+            ast::Condition::RangeCompare {
+                value,
+                bound_a,
+                bound_b,
+                ref affirmed,
+                ref denied,
+            } => {
+                let a = self.value(bound_a, known)?;
+                let b = self.value(bound_b, known)?;
+                let check_val = self.value(value, known)?;
+                let holds = min(a, b) <= check_val && check_val <= max(a, b);
+                (holds, affirmed, denied)
+            }
+            ast::Condition::BinCompare {
+                operation,
+                lhs,
+                rhs,
+                ref affirmed,
+                ref denied,
+            } => {
+                let lhs = self.value(lhs, known)?;
+                let rhs = self.value(rhs, known)?;
+                let holds = match operation {
+                    ast::BinOp::LT => lhs < rhs,
+                    ast::BinOp::GT => lhs > rhs,
+                    ast::BinOp::EQ => lhs == rhs,
+                    ast::BinOp::NE => lhs != rhs,
+                };
+                (holds, affirmed, denied)
+            }
+            ast::Condition::ActionCompare {
+                actor_type,
+                ref action,
+                ref affirmed,
+                ref denied,
+            } => {
+                let action = self.decide_action(action, known)?;
+                let holds = action == self.last_action(actor_type, known)?;
+                (holds, affirmed, denied)
+            }
+        };
+        if holds {
+            self.decide_action(affirmed, known)
+        } else {
+            self.decide_action(denied, known)
+        }
+    }
+
+    fn decide_action(
+        &self,
+        action: &ast::Action,
+        known: u32,
+    ) -> Result<PerformableAction, Input> {
+        match *action {
+            ast::Action::Subcondition(ref sub) => {
+                self.decide_knowing(sub, known)
+            }
+            ref action => Ok(performable(action)),
+        }
+    }
+
+    fn value(&self, value: ast::Value, known: u32) -> Result<usize, Input> {
+        let (input, value) = match value {
+            ast::Value::Literal(x) => return Ok(x as usize),
+            ast::Value::Random => (Input::Random, self.random),
+            ast::Value::Me(attr) => (
+                Input::Attribute(ast::ActorType::Me, attr),
+                self.me[attr as usize],
+            ),
+            ast::Value::Other(attr) => (
+                Input::Attribute(ast::ActorType::Other, attr),
+                self.other[attr as usize],
+            ),
+        };
+        input.known(known).map(|_| value)
+    }
+
+    fn last_action(
+        &self,
+        actor_type: ast::ActorType,
+        known: u32,
+    ) -> Result<PerformableAction, Input> {
+        let last_action = match actor_type {
+            ast::ActorType::Me => self.my_last_action,
+            ast::ActorType::Other => self.other_last_action,
+        };
+        Input::LastAction(actor_type).known(known).map(|_| last_action)
+    }
+
+    fn attributes_mut(
+        &mut self,
+        actor_type: ast::ActorType,
+    ) -> &mut [usize; ATTRIBUTES] {
+        match actor_type {
+            ast::ActorType::Me => &mut self.me,
+            ast::ActorType::Other => &mut self.other,
+        }
+    }
+}
+
+/// Something in a situation that a decision can look at
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Input {
+    Attribute(ast::ActorType, lex::Attribute),
+    Random,
+    LastAction(ast::ActorType),
+}
+
+impl Input {
+    /// Each input is a bit in a set of them
+    fn bit(self) -> u32 {
+        let index = match self {
+            Input::Attribute(actor_type, attr) => {
+                actor_type as usize * ATTRIBUTES + attr as usize
+            }
+            Input::Random => 2 * ATTRIBUTES,
+            Input::LastAction(actor_type) => {
+                2 * ATTRIBUTES + 1 + actor_type as usize
+            }
+        };
+        1 << index
+    }
+
+    fn known(self, known: u32) -> Result<(), Input> {
+        if known & self.bit() == 0 {
+            Err(self)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn performable(action: &ast::Action) -> PerformableAction {
+    match *action {
+        ast::Action::Attack(dmg) => PerformableAction::Attack(dmg),
+        ast::Action::Defend(dmg) => PerformableAction::Defend(dmg),
+        ast::Action::Signal(sig) => PerformableAction::Signal(sig),
+        ast::Action::Eat => PerformableAction::Eat,
+        ast::Action::Take => PerformableAction::Take,
+        ast::Action::Wait => PerformableAction::Wait,
+        ast::Action::Flee => PerformableAction::Flee,
+        ast::Action::Mate => PerformableAction::Mate,
+        ast::Action::Subcondition(_) => {
+            unreachable!("Subconditions decide on an action, they aren't one")
+        }
+    }
+}
+
+/// A situation in which two behaviors decide on different actions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    /// How many thoughts into the cycles they differ, which is always
+    /// 0 for single trees
+    pub thought: usize,
+    pub situation: Situation,
+    pub decisions: (PerformableAction, PerformableAction),
+}
+
+/// Something that decides what a creature does
+pub trait Behavior {
+    /// A situation where `self` and `other` decide differently, if
+    /// there is one
+    fn counterexample(&self, other: &Self) -> Option<Counterexample>;
+}
+
+/// Whether `a` and `b` always decide on the same action, and if not,
+/// a situation where they don't.
+///
+/// Unlike `simplify`, which only rewrites trees in ways it knows are
+/// safe, this tries every situation that could make a difference. The
+/// signal and top item attributes and random numbers only have a few
+/// values, and every other attribute only matters in how it compares
+/// to the literals, those values and the other attributes, so there
+/// are finitely many situations to try. There are exponentially many
+/// in the number of attributes the trees look at, though. Energy is
+/// treated as if it had no limit, so trees that only differ above the
+/// rules' maximum energy count as different.
+pub fn equivalent<B: Behavior + ?Sized>(
+    a: &B,
+    b: &B,
+) -> Result<(), Box<Counterexample>> {
+    match a.counterexample(b) {
+        Some(counterexample) => Err(Box::new(counterexample)),
+        None => Ok(()),
+    }
+}
+
+impl Behavior for ast::Condition {
+    fn counterexample(&self, other: &ast::Condition) -> Option<Counterexample> {
+        if self == other {
+            return None;
+        }
+        let mut inputs = Inputs::default();
+        inputs.add_condition(self);
+        inputs.add_condition(other);
+        inputs.domains().search(
+            (self, other),
+            &mut Situation::default(),
+            0,
+        )
+    }
+}
+
+/// Thoughts a creature cycles through, compared from the first one
+impl Behavior for [Arc<Decision>] {
+    fn counterexample(
+        &self,
+        other: &[Arc<Decision>],
+    ) -> Option<Counterexample> {
+        cycles_counterexample((self, 0), (other, 0))
+    }
+}
+
+/// Compared from where each cycle is now
+impl Behavior for ThoughtCycle {
+    fn counterexample(&self, other: &ThoughtCycle) -> Option<Counterexample> {
+        cycles_counterexample(
+            (&self.thoughts, self.cycle_offset),
+            (&other.thoughts, other.cycle_offset),
+        )
+    }
+}
+
+/// Compares two cycles of thoughts, starting from the given positions,
+/// thought by thought until they're both back where they started
+fn cycles_counterexample(
+    (mine, my_start): (&[Arc<Decision>], usize),
+    (theirs, their_start): (&[Arc<Decision>], usize),
+) -> Option<Counterexample> {
+    let mut thought = 0;
+    loop {
+        let a = &mine[(my_start + thought) % mine.len()];
+        let b = &theirs[(their_start + thought) % theirs.len()];
+        if let Some(mut counterexample) = a.tree.counterexample(&b.tree) {
+            counterexample.thought = thought;
+            return Some(counterexample);
+        }
+        thought += 1;
+        if thought % mine.len() == 0 && thought % theirs.len() == 0 {
+            return None;
+        }
+    }
+}
+
+/// What some trees look at
+#[derive(Default)]
+struct Inputs {
+    attributes: Vec<(ast::ActorType, lex::Attribute)>,
+    literals: BTreeSet<usize>,
+    random: bool,
+    /// The actions that last actions are compared to, for me then for
+    /// my target
+    last_actions: [Vec<PerformableAction>; 2],
+}
+
+impl Inputs {
+    fn add_condition(&mut self, cond: &ast::Condition) {
+        match *cond {
+            ast::Condition::Always(ref action) => self.add_action(action),
+            ast::Condition::RangeCompare {
+                value,
+                bound_a,
+                bound_b,
+                ref affirmed,
+                ref denied,
+            } => {
+                self.add_value(value);
+                self.add_value(bound_a);
+                self.add_value(bound_b);
+                self.add_action(affirmed);
+                self.add_action(denied);
+            }
+            ast::Condition::BinCompare {
+                lhs,
+                rhs,
+                ref affirmed,
+                ref denied,
+                ..
+            } => {
+                self.add_value(lhs);
+                self.add_value(rhs);
+                self.add_action(affirmed);
+                self.add_action(denied);
+            }
+            ast::Condition::ActionCompare {
+                actor_type,
+                ref action,
+                ref affirmed,
+                ref denied,
+            } => {
+                let compared = &mut self.last_actions[actor_type as usize];
+                for decision in possible_decisions(action) {
+                    if !compared.contains(&decision) {
+                        compared.push(decision);
+                    }
+                }
+                self.add_action(action);
+                self.add_action(affirmed);
+                self.add_action(denied);
+            }
+        }
+    }
+
+    fn add_action(&mut self, action: &ast::Action) {
+        if let ast::Action::Subcondition(ref sub) = *action {
+            self.add_condition(sub);
+        }
+    }
+
+    fn add_value(&mut self, value: ast::Value) {
+        let attribute = match value {
+            ast::Value::Literal(x) => {
+                self.literals.insert(x as usize);
+                return;
+            }
+            ast::Value::Random => {
+                self.random = true;
+                return;
+            }
+            ast::Value::Me(attr) => (ast::ActorType::Me, attr),
+            ast::Value::Other(attr) => (ast::ActorType::Other, attr),
+        };
+        if !self.attributes.contains(&attribute) {
+            self.attributes.push(attribute);
+        }
+    }
+
+    /// The values worth trying for everything the trees look at
+    fn domains(&self) -> Domains {
+        let mut constants = self.literals.clone();
+        let mut unbounded = 0;
+        if self.random {
+            constants.extend(random_values());
+        }
+        for &(_, attr) in &self.attributes {
+            match finite_values(attr) {
+                Some(values) => constants.extend(values),
+                None => unbounded += 1,
+            }
+        }
+        let orderings = orderings(&constants, unbounded);
+        let mut domains: Vec<Domain> = self
+            .attributes
+            .iter()
+            .map(|&(actor_type, attr)| {
+                let values = finite_values(attr)
+                    .map_or_else(|| orderings.clone(), Iterator::collect);
+                Domain::Numbers(Input::Attribute(actor_type, attr), values)
+            })
+            .collect();
+        if self.random {
+            domains.push(Domain::Numbers(
+                Input::Random,
+                random_values().collect(),
+            ));
+        }
+        for &actor_type in &[ast::ActorType::Me, ast::ActorType::Other] {
+            let compared = &self.last_actions[actor_type as usize];
+            if !compared.is_empty() {
+                // Standing in for every action that isn't compared to
+                let mut actions = vec![PerformableAction::NoAction];
+                actions.extend(compared);
+                domains.push(Domain::LastActions(actor_type, actions));
+            }
+        }
+        Domains(domains)
+    }
+}
+
+/// Every action a decision could come to
+fn possible_decisions(action: &ast::Action) -> Vec<PerformableAction> {
+    let sub = match *action {
+        ast::Action::Subcondition(ref sub) => sub,
+        ref action => return vec![performable(action)],
+    };
+    match **sub {
+        ast::Condition::Always(ref action) => possible_decisions(action),
+        ast::Condition::RangeCompare {
+            ref affirmed,
+            ref denied,
+            ..
+        }
+        | ast::Condition::BinCompare {
+            ref affirmed,
+            ref denied,
+            ..
+        }
+        | ast::Condition::ActionCompare {
+            ref affirmed,
+            ref denied,
+            ..
+        } => {
+            let mut decisions = possible_decisions(affirmed);
+            decisions.extend(possible_decisions(denied));
+            decisions
+        }
+    }
+}
+
+fn random_values() -> ::std::ops::Range<usize> {
+    0..Gene::MAX_MEANINGFUL_VALUE as usize
+}
+
+/// Every value an attribute can have, for those with only a few
+fn finite_values(attr: lex::Attribute) -> Option<::std::ops::Range<usize>> {
+    match attr {
+        lex::Attribute::Signal => Some(0..lex::Signal::Green as usize + 1),
+        lex::Attribute::TopItem => {
+            Some(0..lex::Item::ExcellentFood as usize + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Enough values for `unbounded` numbers to be in every order they
+/// can be in, relative to each other and to the constants: every
+/// constant, and up to `unbounded` values in each gap around them
+fn orderings(constants: &BTreeSet<usize>, unbounded: usize) -> Vec<usize> {
+    let mut values = Vec::new();
+    let mut gap_start = 0;
+    for &constant in constants {
+        values.extend(gap_start..min(constant, gap_start + unbounded));
+        values.push(constant);
+        gap_start = constant + 1;
+    }
+    values.extend(gap_start..gap_start + unbounded);
+    values
+}
+
+enum Domain {
+    Numbers(Input, Vec<usize>),
+    LastActions(ast::ActorType, Vec<PerformableAction>),
+}
+
+impl Domain {
+    fn input(&self) -> Input {
+        match *self {
+            Domain::Numbers(input, _) => input,
+            Domain::LastActions(actor_type, _) => Input::LastAction(actor_type),
+        }
+    }
+}
+
+struct Domains(Vec<Domain>);
+
+impl Domains {
+    /// Decides with both trees, knowing only the inputs in `known`.
+    /// When one of them looks at something else, each of its values
+    /// is tried in turn, so only inputs that make a difference to the
+    /// path taken through the trees are ever tried.
+    fn search(
+        &self,
+        (a, b): (&ast::Condition, &ast::Condition),
+        situation: &mut Situation,
+        known: u32,
+    ) -> Option<Counterexample> {
+        let decisions = (
+            situation.decide_knowing(a, known),
+            situation.decide_knowing(b, known),
+        );
+        let input = match decisions {
+            (Ok(a), Ok(b)) if a == b => return None,
+            (Ok(a), Ok(b)) => {
+                return Some(Counterexample {
+                    thought: 0,
+                    situation: situation.clone(),
+                    decisions: (a, b),
+                })
+            }
+            (Err(input), _) | (_, Err(input)) => input,
+        };
+        let domain = self
+            .0
+            .iter()
+            .find(|domain| domain.input() == input)
+            .expect("Every input the trees look at has a domain");
+        let known = known | input.bit();
+        let mut counterexample = None;
+        match *domain {
+            Domain::Numbers(Input::Random, ref values) => {
+                for &value in values {
+                    situation.random = value;
+                    counterexample = self.search((a, b), situation, known);
+                    if counterexample.is_some() {
+                        return counterexample;
+                    }
+                }
+                situation.random = 0;
+            }
+            Domain::Numbers(Input::Attribute(actor_type, attr), ref values) => {
+                for &value in values {
+                    situation.attributes_mut(actor_type)[attr as usize] = value;
+                    counterexample = self.search((a, b), situation, known);
+                    if counterexample.is_some() {
+                        return counterexample;
+                    }
+                }
+                situation.attributes_mut(actor_type)[attr as usize] = 0;
+            }
+            Domain::LastActions(actor_type, ref actions) => {
+                for &action in actions {
+                    match actor_type {
+                        ast::ActorType::Me => situation.my_last_action = action,
+                        ast::ActorType::Other => {
+                            situation.other_last_action = action
+                        }
+                    }
+                    counterexample = self.search((a, b), situation, known);
+                    if counterexample.is_some() {
+                        return counterexample;
+                    }
+                }
+                match actor_type {
+                    ast::ActorType::Me => {
+                        situation.my_last_action = PerformableAction::NoAction
+                    }
+                    ast::ActorType::Other => {
+                        situation.other_last_action =
+                            PerformableAction::NoAction
+                    }
+                }
+            }
+            Domain::Numbers(Input::LastAction(_), _) => {
+                unreachable!("Last actions aren't numbers")
+            }
+        }
+        counterexample
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assembly::assemble;
+    use parsing::Parser;
+    use rng::RngState;
+
+    fn decision(assembly: &str) -> Decision {
+        let dna = assemble(assembly).unwrap();
+        Parser::new(&dna, 0).next().unwrap().into_result().ok().unwrap()
+    }
+
+    fn tree(assembly: &str) -> ast::Condition {
+        decision(assembly).tree
+    }
+
+    fn decide(
+        situation: &Situation,
+        tree: &ast::Condition,
+    ) -> PerformableAction {
+        situation.decide_knowing(tree, !0).unwrap()
+    }
+
+    fn cycle(thoughts: &[&str], cycle_offset: usize) -> ThoughtCycle {
+        ThoughtCycle {
+            thoughts: thoughts
+                .iter()
+                .map(|thought| Arc::new(decision(thought)))
+                .collect(),
+            cycle_offset,
+        }
+    }
+
+    #[test]
+    fn simplified_trees_are_equivalent_to_the_originals() {
+        let mut rng = RngState::from_seed(25);
+        let mut compared = 0;
+        for _ in 0..200 {
+            let bases: Vec<i8> = (0..40)
+                .map(|_| {
+                    rng.rand_range(
+                        Gene::STOP_CODON,
+                        Gene::MAX_MEANINGFUL_VALUE + 1,
+                    )
+                })
+                .collect();
+            let dna = DNA::from(bases);
+            for thought in Parser::new(&dna, 0).take(5) {
+                if let Ok(decision) = thought.into_result() {
+                    let original = decision.tree.clone();
+                    assert_eq!(
+                        equivalent(&original, &simplify(decision).tree),
+                        Ok(())
+                    );
+                    compared += 1;
+                }
+            }
+        }
+        assert!(compared > 100);
+    }
+
+    #[test]
+    fn differences_come_with_a_counterexample() {
+        let a = tree("less_than me energy literal 5 mate flee");
+        let b = tree("less_than me energy literal 4 mate flee");
+        let counterexample = equivalent(&a, &b).unwrap_err();
+        let situation = &counterexample.situation;
+        assert_eq!(situation.me[lex::Attribute::Energy as usize], 4);
+        assert_eq!(
+            counterexample.decisions,
+            (PerformableAction::Mate, PerformableAction::Flee)
+        );
+        assert_eq!(decide(situation, &a), PerformableAction::Mate);
+        assert_eq!(decide(situation, &b), PerformableAction::Flee);
+    }
+
+    #[test]
+    fn attributes_are_compared_in_every_order() {
+        // Only differ when my kills and their kills are equal and high
+        let a = tree(
+            "greater_than me kills target kills flee \
+             subcondition less_than me kills literal 6 wait mate",
+        );
+        let b = tree(
+            "greater_than me kills target kills flee \
+             subcondition less_than me kills literal 6 wait \
+             subcondition equal_to me kills target kills use mate",
+        );
+        let situation = equivalent(&a, &b).unwrap_err().situation;
+        let kills = lex::Attribute::Kills as usize;
+        assert!(situation.me[kills] >= 6);
+        assert_eq!(situation.me[kills], situation.other[kills]);
+        // The same decision written differently
+        assert_eq!(
+            equivalent(
+                &tree("less_than me energy target energy mate flee"),
+                &tree("greater_than target energy me energy mate flee"),
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn last_actions_are_compared() {
+        let a = tree("me_last_act attack fire flee mate");
+        let b = tree("always mate");
+        let counterexample = equivalent(&a, &b).unwrap_err();
+        assert_eq!(
+            counterexample.situation.my_last_action,
+            PerformableAction::Attack(lex::DamageType::Fire)
+        );
+        assert_eq!(
+            equivalent(&tree("target_last_act wait mate mate"), &b),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn cycles_are_compared_thought_by_thought() {
+        let two = cycle(&["always mate", "always flee"], 0);
+        let four = cycle(
+            &[
+                "always mate",
+                "always flee",
+                "less_than random literal 0 wait mate",
+                "always flee",
+            ],
+            0,
+        );
+        assert_eq!(equivalent(&two, &four), Ok(()));
+        let later = cycle(&["always mate", "always flee"], 1);
+        assert_eq!(equivalent(&two, &later).unwrap_err().thought, 0);
+        let three =
+            cycle(&["always mate", "always flee", "always mate"], 0);
+        let counterexample = equivalent(&two, &three).unwrap_err();
+        assert_eq!(counterexample.thought, 3);
+        assert_eq!(
+            counterexample.decisions,
+            (PerformableAction::Flee, PerformableAction::Mate)
+        );
+    }
+}
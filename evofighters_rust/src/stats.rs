@@ -1,3 +1,11 @@
+use std::fmt;
+use std::str::FromStr;
+
+use num::FromPrimitive;
+
+use dna::lex::{DamageType, Signal};
+use eval::PerformableAction;
+
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, Default)]
 pub struct GlobalStatistics {
     pub mutations: usize,
@@ -5,6 +13,13 @@ pub struct GlobalStatistics {
     pub feeders_eaten: usize,
     pub kills: usize,
     pub rounds: usize,
+    /// Events run, which is also the number of the next event
+    #[serde(default)]
+    pub events: u64,
+    #[serde(default)]
+    pub actions: ActionCounts,
+    #[serde(default)]
+    pub outcomes: OutcomeCounts,
 }
 
 impl GlobalStatistics {
@@ -18,6 +33,152 @@ impl GlobalStatistics {
         self.feeders_eaten += other.feeders_eaten;
         self.kills += other.kills;
         self.rounds += other.rounds;
+        self.events += other.events;
+        self.actions.absorb(&other.actions);
+        self.outcomes.absorb(&other.outcomes);
+    }
+
+    /// What creatures did and how it turned out, for showing to
+    /// someone watching a run
+    pub fn behaviour(&self) -> String {
+        format!("Actions: {}\nOutcomes: {}", self.actions, self.outcomes)
+    }
+}
+
+/// How often creatures chose each action, one count per creature per
+/// round. Feeders don't choose anything, so they aren't counted.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, Default)]
+pub struct ActionCounts {
+    /// Indexed by `DamageType`
+    pub attacks: [usize; 3],
+    /// Indexed by `DamageType`
+    pub defends: [usize; 3],
+    /// Indexed by `Signal`, starting from red
+    pub signals: [usize; 6],
+    pub eat: usize,
+    pub take: usize,
+    pub wait: usize,
+    pub flee: usize,
+    pub mate: usize,
+}
+
+impl ActionCounts {
+    pub fn count(&mut self, action: PerformableAction) {
+        match action {
+            PerformableAction::Attack(dmg) => self.attacks[dmg as usize] += 1,
+            PerformableAction::Defend(dmg) => self.defends[dmg as usize] += 1,
+            PerformableAction::Signal(sig) => {
+                self.signals[sig as usize - Signal::Red as usize] += 1
+            }
+            PerformableAction::Eat => self.eat += 1,
+            PerformableAction::Take => self.take += 1,
+            PerformableAction::Wait => self.wait += 1,
+            PerformableAction::Flee => self.flee += 1,
+            PerformableAction::Mate => self.mate += 1,
+            PerformableAction::NoAction => (),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.attacks.iter().sum::<usize>()
+            + self.defends.iter().sum::<usize>()
+            + self.signals.iter().sum::<usize>()
+            + self.eat
+            + self.take
+            + self.wait
+            + self.flee
+            + self.mate
+    }
+
+    fn absorb(&mut self, other: &ActionCounts) {
+        add_counts(&mut self.attacks, &other.attacks);
+        add_counts(&mut self.defends, &other.defends);
+        add_counts(&mut self.signals, &other.signals);
+        self.eat += other.eat;
+        self.take += other.take;
+        self.wait += other.wait;
+        self.flee += other.flee;
+        self.mate += other.mate;
+    }
+}
+
+fn add_counts(counts: &mut [usize], other: &[usize]) {
+    for (count, other) in counts.iter_mut().zip(other) {
+        *count += other;
+    }
+}
+
+const DAMAGE_TYPES: [DamageType; 3] =
+    [DamageType::Fire, DamageType::Ice, DamageType::Electricity];
+
+/// Shows each action as a percentage of all actions taken
+impl fmt::Display for ActionCounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.total().max(1) as f64;
+        let percent = |count: usize| 100.0 * count as f64 / total;
+        write!(f, "attack")?;
+        for &dmg in &DAMAGE_TYPES {
+            write!(f, " {} {:.1}%", dmg, percent(self.attacks[dmg as usize]))?;
+        }
+        write!(f, ", defend")?;
+        for &dmg in &DAMAGE_TYPES {
+            write!(f, " {} {:.1}%", dmg, percent(self.defends[dmg as usize]))?;
+        }
+        write!(f, ", signal")?;
+        for (i, &count) in self.signals.iter().enumerate() {
+            let color = Signal::from_usize(i + Signal::Red as usize).unwrap();
+            write!(f, " {} {:.1}%", color, percent(count))?;
+        }
+        write!(
+            f,
+            ", eat {:.1}%, take {:.1}%, wait {:.1}%, flee {:.1}%, \
+             mate {:.1}%",
+            percent(self.eat),
+            percent(self.take),
+            percent(self.wait),
+            percent(self.flee),
+            percent(self.mate)
+        )
+    }
+}
+
+/// How encounters and mating attempts turned out, besides the kills
+/// and births counted in `GlobalStatistics`
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, Default)]
+pub struct OutcomeCounts {
+    /// Encounters that ran out of rounds
+    pub timeouts: usize,
+    pub flee_successes: usize,
+    /// Encounters where both fighters died
+    pub mutual_deaths: usize,
+    /// Children that didn't live because their DNA was invalid
+    pub stillbirths: usize,
+    /// Successful mating rolls where someone couldn't pay their share
+    pub failed_mating_payments: usize,
+}
+
+impl OutcomeCounts {
+    fn absorb(&mut self, other: &OutcomeCounts) {
+        self.timeouts += other.timeouts;
+        self.flee_successes += other.flee_successes;
+        self.mutual_deaths += other.mutual_deaths;
+        self.stillbirths += other.stillbirths;
+        self.failed_mating_payments += other.failed_mating_payments;
+    }
+}
+
+impl fmt::Display for OutcomeCounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "timeouts {}, fled {}, mutual deaths {}, stillborn {}, \
+             unpaid matings {}",
+            self.timeouts,
+            self.flee_successes,
+            self.mutual_deaths,
+            self.stillbirths,
+            self.failed_mating_payments
+        )
     }
 }
 
@@ -28,3 +189,70 @@ pub struct CreatureStats {
     pub survived: usize,
     pub eaten: usize,
 }
+
+/// Ways of deciding which creatures are the most notable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ranking {
+    Kills,
+    Children,
+    Survived,
+}
+
+impl FromStr for Ranking {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Ranking, String> {
+        match s {
+            "kills" => Ok(Ranking::Kills),
+            "children" => Ok(Ranking::Children),
+            "survived" => Ok(Ranking::Survived),
+            _ => Err(format!("Can't rank creatures by {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Ranking {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Ranking::Kills => write!(f, "kills"),
+            Ranking::Children => write!(f, "children"),
+            Ranking::Survived => write!(f, "encounters survived"),
+        }
+    }
+}
+
+impl Ranking {
+    pub const ALL: [Ranking; 3] =
+        [Ranking::Kills, Ranking::Children, Ranking::Survived];
+
+    pub fn score(&self, stats: &CreatureStats) -> usize {
+        match *self {
+            Ranking::Kills => stats.kills,
+            Ranking::Children => stats.num_children,
+            Ranking::Survived => stats.survived,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_are_counted_by_type_and_color() {
+        let mut counts = ActionCounts::default();
+        counts.count(PerformableAction::Attack(DamageType::Ice));
+        counts.count(PerformableAction::Signal(Signal::Red));
+        counts.count(PerformableAction::Signal(Signal::Green));
+        counts.count(PerformableAction::NoAction);
+        let mut stats = GlobalStatistics::new();
+        stats.actions.count(PerformableAction::Attack(DamageType::Ice));
+        stats.absorb(GlobalStatistics {
+            actions: counts,
+            ..GlobalStatistics::new()
+        });
+        assert_eq!(stats.actions.attacks, [0, 2, 0]);
+        assert_eq!(stats.actions.signals, [1, 0, 0, 0, 0, 1]);
+        assert_eq!(stats.actions.total(), 4);
+    }
+}
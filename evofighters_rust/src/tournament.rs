@@ -0,0 +1,337 @@
+//! Round robin tournaments between chosen creatures. Every fight is
+//! between fresh copies of the entrants, so nothing that happens in a
+//! tournament changes the population they came from.
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use arena::Encounter;
+use creatures::{Creature, Creatures, IDGiver};
+use rng::RngState;
+use rules::Rules;
+use saver::Settings;
+use stats::Ranking;
+
+/// The `k` best creatures in the population by `ranking`, best first
+pub fn top_creatures(
+    population: &Creatures,
+    ranking: Ranking,
+    k: usize,
+) -> Vec<Creature> {
+    let mut creatures: Vec<&Creature> = population.iter().collect();
+    // Older creatures win ties, they've had more time to prove it
+    let key = |creature: &&Creature| {
+        let score = ranking.score(&creature.stats);
+        (Reverse(score), creature.generation, creature.id)
+    };
+    // Only the top k need sorting, which matters for a big population
+    if k < creatures.len() {
+        creatures.select_nth_unstable_by_key(k, key);
+        creatures.truncate(k);
+    }
+    creatures.sort_by_key(key);
+    creatures.into_iter().cloned().collect()
+}
+
+/// How one entrant did against another
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    pub children: usize,
+}
+
+impl Record {
+    fn absorb(&mut self, other: Record) {
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.draws += other.draws;
+        self.children += other.children;
+    }
+}
+
+pub struct Tournament {
+    entrants: Vec<Creature>,
+    /// `records[i][j]` is how entrant `i` did against entrant `j`
+    records: Vec<Vec<Record>>,
+    elo: Vec<f64>,
+}
+
+impl Tournament {
+    const INITIAL_ELO: f64 = 1500.0;
+    const ELO_K_FACTOR: f64 = 32.0;
+
+    /// Has every pair of entrants fight `bouts` times, taking turns
+    /// being the first fighter. Fails unless there are at least two
+    /// different entrants, none of which are feeders.
+    pub fn run(
+        entrants: Vec<Creature>,
+        bouts: usize,
+        settings: &Settings,
+        rules: &Rules,
+        rng: &mut RngState,
+    ) -> Result<Tournament, String> {
+        Tournament::check_entrants(&entrants)?;
+        let n = entrants.len();
+        let mut tournament = Tournament {
+            entrants,
+            records: vec![vec![Record::default(); n]; n],
+            elo: vec![Tournament::INITIAL_ELO; n],
+        };
+        // Children born in a tournament are thrown away, so it
+        // doesn't matter what ids they get
+        let mut id_giver = IDGiver::unthreaded();
+        for i in 0..n {
+            for j in (i + 1)..n {
+                for bout in 0..bouts {
+                    let (first, second) =
+                        if bout % 2 == 0 { (i, j) } else { (j, i) };
+                    let mut enc = Encounter::new(
+                        tournament.entrants[first].clone(),
+                        tournament.entrants[second].clone(),
+                        settings,
+                        rules,
+                        rng,
+                        &mut id_giver,
+                    );
+                    enc.encounter();
+                    // The fighters may have been swapped around during
+                    // the encounter, so go by id
+                    let alive = |idx: usize| {
+                        let id = tournament.entrants[idx].id;
+                        if enc.p1.id == id {
+                            enc.p1.alive()
+                        } else {
+                            enc.p2.alive()
+                        }
+                    };
+                    let score = match (alive(i), alive(j)) {
+                        (true, false) => 1.0,
+                        (false, true) => 0.0,
+                        _ => 0.5,
+                    };
+                    let children = enc.children.len();
+                    tournament.record(i, j, score, children);
+                }
+            }
+        }
+        Ok(tournament)
+    }
+
+    /// Bouts are scored by which fighter is still alive, which only
+    /// works if every entrant is a different creature
+    fn check_entrants(entrants: &[Creature]) -> Result<(), String> {
+        if entrants.len() < 2 {
+            return Err("A tournament needs at least two creatures".to_owned());
+        }
+        for (i, entrant) in entrants.iter().enumerate() {
+            if entrant.is_feeder() {
+                return Err("Feeders can't enter a tournament".to_owned());
+            }
+            if entrants[..i].iter().any(|other| other.id == entrant.id) {
+                return Err(format!("{} is entered more than once", entrant));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a bout between entrants `i` and `j`, where `score` is
+    /// 1 if `i` won, 0 if `j` won and 0.5 for a draw
+    fn record(&mut self, i: usize, j: usize, score: f64, children: usize) {
+        let mut record = Record {
+            children,
+            ..Record::default()
+        };
+        if score == 1.0 {
+            record.wins = 1;
+        } else if score == 0.0 {
+            record.losses = 1;
+        } else {
+            record.draws = 1;
+        }
+        self.records[i][j].absorb(record);
+        self.records[j][i].absorb(Record {
+            wins: record.losses,
+            losses: record.wins,
+            ..record
+        });
+
+        let expected =
+            1.0 / (1.0 + 10f64.powf((self.elo[j] - self.elo[i]) / 400.0));
+        let change = Tournament::ELO_K_FACTOR * (score - expected);
+        self.elo[i] += change;
+        self.elo[j] -= change;
+    }
+
+    /// Each entrant's overall record and Elo rating, best rated first
+    pub fn ranking(&self) -> Vec<(&Creature, Record, f64)> {
+        let mut ranking: Vec<_> = self.entrants
+            .iter()
+            .zip(&self.records)
+            .zip(&self.elo)
+            .map(|((entrant, records), &elo)| {
+                let mut total = Record::default();
+                for &record in records {
+                    total.absorb(record);
+                }
+                (entrant, total, elo)
+            })
+            .collect();
+        ranking.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
+        ranking
+    }
+}
+
+/// Shows the matrix of results, then the ranking
+impl fmt::Display for Tournament {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const WIDTH: usize = 15;
+        writeln!(f, "Wins/losses/draws/children of each row vs. column")?;
+        write!(f, "{:>width$}", "", width = WIDTH)?;
+        for entrant in &self.entrants {
+            write!(f, "{:>width$}", entrant.id.to_string(), width = WIDTH)?;
+        }
+        writeln!(f)?;
+        for (entrant, records) in self.entrants.iter().zip(&self.records) {
+            write!(f, "{:>width$}", entrant.id.to_string(), width = WIDTH)?;
+            for (opponent, record) in self.entrants.iter().zip(records) {
+                let cell = if opponent.id == entrant.id {
+                    "-".to_owned()
+                } else {
+                    format!(
+                        "{}/{}/{}/{}",
+                        record.wins,
+                        record.losses,
+                        record.draws,
+                        record.children
+                    )
+                };
+                write!(f, "{:>width$}", cell, width = WIDTH)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Rank  Creature          Elo  Wins Losses Draws Children")?;
+        for (rank, (entrant, total, elo)) in
+            self.ranking().into_iter().enumerate()
+        {
+            writeln!(
+                f,
+                "{:>4}  {:<15} {:>5.0} {:>5} {:>6} {:>5} {:>8}",
+                rank + 1,
+                entrant.to_string(),
+                elo,
+                total.wins,
+                total.losses,
+                total.draws,
+                total.children
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use creatures::CreatureID;
+
+    fn seed_creatures(n: usize) -> Vec<Creature> {
+        let rules = Rules::default();
+        let mut id_giver = IDGiver::unthreaded();
+        (0..n)
+            .map(|_| {
+                Creature::seed_creature(id_giver.next_creature_id(), &rules)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn top_creatures_are_the_best_in_order() {
+        let rules = Rules::default();
+        let mut population =
+            Creatures::new(20, RngState::from_seed(3), &rules);
+        let ids: Vec<CreatureID> =
+            population.iter().map(|creature| creature.id).collect();
+        for (kills, &id) in ids.iter().enumerate() {
+            let mut creature = population.take(id).unwrap();
+            creature.stats.kills = kills % 7;
+            population.absorb(creature);
+        }
+        let mut best: Vec<&Creature> = population.iter().collect();
+        best.sort_by_key(|creature| {
+            (Reverse(creature.stats.kills), creature.generation, creature.id)
+        });
+        for &k in &[0, 3, 20, 25] {
+            let top: Vec<CreatureID> =
+                top_creatures(&population, Ranking::Kills, k)
+                    .iter()
+                    .map(|creature| creature.id)
+                    .collect();
+            let expected: Vec<CreatureID> =
+                best.iter().take(k).map(|creature| creature.id).collect();
+            assert_eq!(top, expected);
+        }
+    }
+
+    #[test]
+    fn records_are_mirrored_between_opponents() {
+        let tournament = Tournament::run(
+            seed_creatures(3),
+            10,
+            &Settings::default(),
+            &Rules::default(),
+            &mut RngState::from_seed(7),
+        ).unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                let ij = tournament.records[i][j];
+                let ji = tournament.records[j][i];
+                assert_eq!(ij.wins, ji.losses);
+                assert_eq!(ij.draws, ji.draws);
+                assert_eq!(ij.children, ji.children);
+                if i != j {
+                    assert_eq!(ij.wins + ij.losses + ij.draws, 10);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn entrants_must_be_different_creatures() {
+        let run = |entrants: Vec<Creature>| {
+            Tournament::run(
+                entrants,
+                1,
+                &Settings::default(),
+                &Rules::default(),
+                &mut RngState::default(),
+            ).err()
+        };
+        let mut entrants = seed_creatures(2);
+        entrants.push(entrants[0].clone());
+        assert_eq!(
+            run(entrants).unwrap(),
+            "[Creature 1] is entered more than once"
+        );
+        let mut entrants = seed_creatures(2);
+        entrants.push(Creature::feeder());
+        assert_eq!(run(entrants).unwrap(), "Feeders can't enter a tournament");
+        assert!(run(seed_creatures(1)).is_some());
+        assert!(run(seed_creatures(2)).is_none());
+    }
+
+    #[test]
+    fn elo_moves_towards_the_winner() {
+        let mut tournament = Tournament {
+            entrants: seed_creatures(2),
+            records: vec![vec![Record::default(); 2]; 2],
+            elo: vec![Tournament::INITIAL_ELO; 2],
+        };
+        tournament.record(0, 1, 1.0, 0);
+        assert_eq!(tournament.elo[0], 1516.0);
+        assert_eq!(tournament.elo[1], 1484.0);
+        assert_eq!(tournament.ranking()[0].0.id, tournament.entrants[0].id);
+    }
+}
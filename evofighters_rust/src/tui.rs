@@ -0,0 +1,276 @@
+//! A full-screen terminal view of a running simulation, for watching
+//! it more closely than the one line status allows. It draws whatever
+//! the simulation reports to its `Dashboard`, and sends key presses
+//! back the same way the buttons on the HTTP dashboard do.
+
+use std::collections::VecDeque;
+use std::io;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use termion;
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use termion::{clear, cursor, style};
+
+use dashboard::{Dashboard, Status};
+
+const BARS: [char; 8] =
+    ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Draws `values` as a line of bars, scaled so the largest fills a
+/// whole character. Only the last `width` values fit.
+fn sparkline(values: &VecDeque<u64>, width: usize) -> String {
+    let shown = values.iter().skip(values.len().saturating_sub(width));
+    let max = shown.clone().cloned().max().unwrap_or(0);
+    shown
+        .map(|&value| {
+            let level = (value * (BARS.len() as u64 - 1)).checked_div(max);
+            BARS[level.unwrap_or(0) as usize]
+        })
+        .collect()
+}
+
+/// Cuts `line` down to `width` characters
+fn fit(line: &str, width: usize) -> String {
+    line.chars().take(width).collect()
+}
+
+/// The recent past of the numbers that get sparklines, one sample a
+/// second
+struct History {
+    population: VecDeque<u64>,
+    feeders: VecDeque<u64>,
+    births: VecDeque<u64>,
+    kills: VecDeque<u64>,
+    last_sample: Option<(Instant, Status)>,
+}
+
+impl History {
+    /// How many samples are kept, enough for a very wide terminal
+    const LENGTH: usize = 500;
+    const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+    fn new() -> History {
+        History {
+            population: VecDeque::new(),
+            feeders: VecDeque::new(),
+            births: VecDeque::new(),
+            kills: VecDeque::new(),
+            last_sample: None,
+        }
+    }
+
+    /// Takes a sample if it's been long enough since the last one.
+    /// Births and kills are per second since the last sample.
+    fn sample(&mut self, status: Status) {
+        let (births, kills) = match self.last_sample {
+            Some((at, _)) if at.elapsed() < History::SAMPLE_INTERVAL => {
+                return
+            }
+            Some((at, last)) => {
+                let elapsed = at.elapsed();
+                let secs = elapsed.as_secs() as f64
+                    + f64::from(elapsed.subsec_nanos()) / 1_000_000_000.0;
+                let per_second = |now: usize, then: usize| {
+                    (now.saturating_sub(then) as f64 / secs) as u64
+                };
+                (
+                    per_second(
+                        status.stats.children_born,
+                        last.stats.children_born,
+                    ),
+                    per_second(status.stats.kills, last.stats.kills),
+                )
+            }
+            None => (0, 0),
+        };
+        History::push(&mut self.population, status.population as u64);
+        History::push(&mut self.feeders, status.feeders as u64);
+        History::push(&mut self.births, births);
+        History::push(&mut self.kills, kills);
+        self.last_sample = Some((Instant::now(), status));
+    }
+
+    fn push(series: &mut VecDeque<u64>, value: u64) {
+        series.push_back(value);
+        if series.len() > History::LENGTH {
+            series.pop_front();
+        }
+    }
+}
+
+/// The terminal UI, drawing on its own thread until it's stopped
+pub struct Tui {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<io::Result<()>>,
+}
+
+impl Tui {
+    /// How many of the last messages are worth printing once the
+    /// terminal is given back
+    pub const FINAL_MESSAGES: usize = 10;
+    /// How long to wait between redraws
+    const FRAME: Duration = Duration::from_millis(100);
+    /// How many lines of each leader's strategy are shown
+    const STRATEGY_LINES: usize = 6;
+    const HELP: &'static str = "p: pause/resume  s: save  q: save and quit";
+
+    /// Takes over the terminal and starts redrawing it. Anything the
+    /// simulation would print goes to the dashboard's messages
+    /// instead until the UI is stopped.
+    pub fn start(dashboard: Arc<Dashboard>) -> io::Result<Tui> {
+        let screen = AlternateScreen::from(io::stdout().into_raw_mode()?);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+        dashboard.set_owns_terminal(true);
+        let handle = thread::spawn(move || {
+            let result = Tui::run(&dashboard, screen, &stopped);
+            dashboard.set_owns_terminal(false);
+            result
+        });
+        Ok(Tui { stop, handle })
+    }
+
+    /// Gives the terminal back
+    pub fn stop(self) -> io::Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().expect("Terminal UI thread panicked")
+    }
+
+    fn run<W: Write>(
+        dashboard: &Dashboard,
+        mut screen: W,
+        stop: &AtomicBool,
+    ) -> io::Result<()> {
+        let mut keys = termion::async_stdin().keys();
+        let mut history = History::new();
+        write!(screen, "{}{}", cursor::Hide, clear::All)?;
+        while !stop.load(Ordering::Relaxed) {
+            for key in keys.by_ref() {
+                Tui::handle_key(dashboard, key?);
+            }
+            history.sample(dashboard.status());
+            let (width, height) = termion::terminal_size()?;
+            let (width, height) = (width as usize, height as usize);
+            let lines = Tui::layout(dashboard, &history, width, height);
+            for (row, line) in lines.iter().take(height).enumerate() {
+                write!(
+                    screen,
+                    "{}{}{}",
+                    cursor::Goto(1, row as u16 + 1),
+                    fit(line, width),
+                    clear::UntilNewline
+                )?;
+            }
+            write!(screen, "{}", clear::AfterCursor)?;
+            screen.flush()?;
+            thread::sleep(Tui::FRAME);
+        }
+        write!(screen, "{}", cursor::Show)?;
+        screen.flush()
+    }
+
+    fn handle_key(dashboard: &Dashboard, key: Key) {
+        match key {
+            Key::Char('p') => dashboard.toggle_pause(),
+            Key::Char('s') => {
+                dashboard.request_save();
+                dashboard.log("Save requested");
+            }
+            // Raw mode swallows ctrl-c, so it has to be handled here
+            Key::Char('q') | Key::Ctrl('c')
+                if !dashboard.quit_requested() =>
+            {
+                dashboard.request_quit();
+                dashboard.log("Saving and quitting...");
+            }
+            _ => (),
+        }
+    }
+
+    /// Everything to draw, one string per line. Lines that are too
+    /// long or too many are cut off when drawn.
+    fn layout(
+        dashboard: &Dashboard,
+        history: &History,
+        width: usize,
+        height: usize,
+    ) -> Vec<String> {
+        let status = dashboard.status();
+        let state = if dashboard.quit_requested() {
+            "QUITTING"
+        } else if dashboard.is_paused() {
+            "PAUSED"
+        } else {
+            "running"
+        };
+        let mut lines = vec![
+            format!(
+                "{}EvoFighters{} [{}]  Events: {}  eps: {}  {}",
+                style::Bold,
+                style::Reset,
+                state,
+                status.stats.events,
+                status.events_per_second,
+                Tui::HELP
+            ),
+            String::new(),
+        ];
+        // Labels and numbers take 22 characters
+        let spark_width = width.saturating_sub(22);
+        for &(label, series) in &[
+            ("Creatures", &history.population),
+            ("Feeders", &history.feeders),
+            ("Births/s", &history.births),
+            ("Kills/s", &history.kills),
+        ] {
+            lines.push(format!(
+                "{:<10}{:>10}  {}",
+                label,
+                series.back().cloned().unwrap_or(0),
+                sparkline(series, spark_width)
+            ));
+        }
+        lines.push(String::new());
+        lines.push(format!("{}Top killers{}", style::Bold, style::Reset));
+        for leader in dashboard.leaders() {
+            lines.push(leader.summary);
+            let mut strategy = leader.strategy.into_iter();
+            lines.extend(
+                strategy
+                    .by_ref()
+                    .take(Tui::STRATEGY_LINES)
+                    .map(|line| format!("  {}", line)),
+            );
+            if strategy.next().is_some() {
+                lines.push("  ...".to_owned());
+            }
+        }
+        lines.push(String::new());
+        lines.push(format!("{}Messages{}", style::Bold, style::Reset));
+        let room = height.saturating_sub(lines.len()).max(1);
+        lines.extend(dashboard.messages(room));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparklines_scale_to_the_largest_value() {
+        let values: VecDeque<u64> = vec![100, 0, 4, 8].into_iter().collect();
+        assert_eq!(sparkline(&values, 3), "▁▄█");
+        assert_eq!(sparkline(&values, 10).chars().count(), 4);
+        let zeros: VecDeque<u64> = vec![0, 0].into_iter().collect();
+        assert_eq!(sparkline(&zeros, 10), "▁▁");
+    }
+}